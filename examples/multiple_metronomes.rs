@@ -1,10 +1,10 @@
-use metronome_rs::{Metronome, stop_global_metronome};
+use metronome_rs::{Metronome, MetronomeMixer, stop_global_metronome};
 use std::thread;
 use std::time::Duration;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Multiple Metronomes Demo");
-    println!("This demonstrates that only one metronome can play at a time (global singleton).");
+    println!("Metronome::start plays one metronome at a time (global singleton).");
 
     println!("\n1. Creating first metronome at 100 BPM...");
     let metronome1 = Metronome::new(100.0, None)?;
@@ -31,9 +31,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Give it a moment to fully stop
     thread::sleep(Duration::from_millis(200));
 
-    println!("All metronomes stopped!");
     println!("First metronome playing: {}", metronome1.is_playing());
     println!("Second metronome playing: {}", metronome2.is_playing());
 
+    println!("\n4. Layering both metronomes through a mixer...");
+    let mixer = MetronomeMixer::new()?;
+    let slow = mixer.add(&metronome1, 1.0);
+    let fast = mixer.add(&metronome2, 0.4);
+    mixer.start_all()?;
+    println!("Both metronomes are playing together, the faster one at 40% gain.");
+
+    thread::sleep(Duration::from_millis(5000));
+
+    println!("\n5. Bringing the faster metronome up to full gain...");
+    fast.set_gain(1.0);
+    thread::sleep(Duration::from_millis(3000));
+
+    println!("\n6. Stopping the mixer...");
+    mixer.stop_all();
+    thread::sleep(Duration::from_millis(200));
+
+    println!("All metronomes stopped!");
+    println!("Slow channel playing: {}", slow.is_playing());
+    println!("Fast channel playing: {}", fast.is_playing());
+
     Ok(())
 }
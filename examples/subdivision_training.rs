@@ -24,30 +24,38 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Example 3: Increase tempo gradually
     println!("\n3. Step 3: Gradually increase tempo with subdivisions");
-    let tempos = [70.0, 80.0, 90.0, 100.0];
-
-    for &tempo in &tempos {
-        println!("   Practicing at {} BPM with eighth notes", tempo);
-        start_metronome_with_eighth_notes(tempo, Some(4))?;
-        thread::sleep(Duration::from_millis(4000)); // 1 measure at each tempo
-        stop_global_metronome();
-        thread::sleep(Duration::from_millis(500));
+    println!("   The speed trainer adds 10 BPM every bar without stopping the click");
+    let trainer = SpeedTrainer::new(70.0, 10.0, 1, 100.0);
+    let mut metronome =
+        Metronome::new_with_accent(70.0, Some(4), AccentConfig::with_eighth_notes())?;
+    metronome.set_speed_trainer(Some(trainer));
+    let events = metronome.events();
+    metronome.start_for_bars(4)?;
+
+    // The event stream ends once the fourth bar has played
+    for event in events.iter() {
+        if event.beat_in_bar == 0 && event.subdivision == 0 {
+            println!(
+                "   Bar {}: {} BPM with eighth notes",
+                event.bar + 1,
+                trainer.bpm_at_bar(event.bar)
+            );
+        }
     }
 
     // Example 4: Triplet practice
     println!("\n4. Triplet training progression");
     println!("   Learning to feel triplets against quarter notes");
 
-    println!("   a) Quarter notes only (80 BPM)");
-    play_metronome_for_duration(80.0, Some(4), 3000)?;
-
-    thread::sleep(Duration::from_millis(500));
-
-    println!("   b) Same tempo with triplet subdivisions");
+    println!("   a) Quarter notes only for two bars (80 BPM)");
+    println!("   b) Then triplet subdivisions at the same tempo, without a break");
     println!("      Play quarter notes, listen to triplet subdivisions");
-    start_metronome_with_triplets(80.0, Some(4))?;
-    thread::sleep(Duration::from_millis(6000));
-    stop_global_metronome();
+    let mut metronome = Metronome::new(80.0, Some(4))?;
+    metronome.set_subdivision_ladder(Some(SubdivisionLadder::new(vec![1, 3], 2)));
+    let events = metronome.events();
+    metronome.start_for_bars(4)?;
+    // The event stream ends once the last bar has played
+    for _ in events.iter() {}
 
     thread::sleep(Duration::from_millis(1000));
 
@@ -73,6 +81,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     thread::sleep(Duration::from_millis(6000));
     stop_global_metronome();
 
+    thread::sleep(Duration::from_millis(1000));
+
+    // Example 7: The full subdivision ladder
+    println!(
+        "\n7. Subdivision ladder: quarters, eighths, triplets, sixteenths, quintuplets and back"
+    );
+    println!("   Each level lasts one bar at 60 BPM, and the pulse never stops");
+    let ladder = SubdivisionLadder::standard(1);
+    let mut metronome = Metronome::new(60.0, Some(4))?;
+    metronome.set_subdivision_ladder(Some(ladder.clone()));
+    let events = metronome.events();
+    metronome.start_for_bars(8)?;
+    for event in events.iter() {
+        if event.beat_in_bar == 0 && event.subdivision == 0 {
+            println!(
+                "   Bar {}: {} clicks per beat",
+                event.bar + 1,
+                ladder.subdivisions_at_bar(event.bar)
+            );
+        }
+    }
+
     println!("\nTraining complete!");
     println!("\nTips for using subdivisions effectively:");
     println!("- Start slow and gradually increase tempo");
@@ -1,3 +1,9 @@
+use crate::schedule::{ClickKind, ScheduledClick};
+use crate::voice::Voice;
+
+/// Volume of ghost beats when no sound is set for them
+const GHOST_VOLUME: f32 = 0.3;
+
 /// Wave types available for metronome sounds.
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub enum WaveType {
@@ -12,6 +18,86 @@ pub enum WaveType {
     Triangle,
 }
 
+/// How strongly a beat of the measure is accented.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AccentLevel {
+    /// Primary accent, played with the accent sound by default
+    Strong,
+    /// Secondary accent, pitched between the accent and regular sounds by default
+    Medium,
+    /// Unaccented beat, played with the regular sound by default
+    Weak,
+    /// Barely audible beat, played with the regular sound at low volume by default
+    Ghost,
+    /// Silent beat
+    Mute,
+}
+
+impl AccentLevel {
+    /// Gets the level a click of the given kind is played at, or `None` for subdivisions.
+    pub(crate) const fn for_kind(kind: ClickKind) -> Option<Self> {
+        match kind {
+            ClickKind::Accent => Some(Self::Strong),
+            ClickKind::SecondaryAccent => Some(Self::Medium),
+            ClickKind::Regular => Some(Self::Weak),
+            ClickKind::Subdivision => None,
+        }
+    }
+}
+
+/// Sounds overriding the default sound of each accent level.
+///
+/// Levels without a sound of their own use a sound derived from the accent and
+/// regular settings of their [`AccentConfig`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LevelSounds {
+    /// Sound for [`AccentLevel::Strong`] beats
+    pub strong: Option<Voice>,
+    /// Sound for [`AccentLevel::Medium`] beats
+    pub medium: Option<Voice>,
+    /// Sound for [`AccentLevel::Weak`] beats
+    pub weak: Option<Voice>,
+    /// Sound for [`AccentLevel::Ghost`] beats
+    pub ghost: Option<Voice>,
+}
+
+impl LevelSounds {
+    /// Creates a set of level sounds that all use their defaults.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            strong: None,
+            medium: None,
+            weak: None,
+            ghost: None,
+        }
+    }
+
+    /// Gets the sound set for a level, if any.
+    #[must_use]
+    pub const fn get(&self, level: AccentLevel) -> Option<&Voice> {
+        match level {
+            AccentLevel::Strong => self.strong.as_ref(),
+            AccentLevel::Medium => self.medium.as_ref(),
+            AccentLevel::Weak => self.weak.as_ref(),
+            AccentLevel::Ghost => self.ghost.as_ref(),
+            AccentLevel::Mute => None,
+        }
+    }
+
+    /// Sets the sound for a level. Muted beats never make a sound, so they can't have one.
+    pub const fn set(&mut self, level: AccentLevel, voice: Voice) {
+        let slot = match level {
+            AccentLevel::Strong => &mut self.strong,
+            AccentLevel::Medium => &mut self.medium,
+            AccentLevel::Weak => &mut self.weak,
+            AccentLevel::Ghost => &mut self.ghost,
+            AccentLevel::Mute => return,
+        };
+        *slot = Some(voice);
+    }
+}
+
 /// Configuration for accent beats in the metronome.
 #[derive(Clone, Debug)]
 pub struct AccentConfig {
@@ -37,6 +123,11 @@ pub struct AccentConfig {
     pub subdivision_wave_type: WaveType,
     /// Volume multiplier for subdivisions (0.0 to 1.0, where 1.0 is same volume as regular beats)
     pub subdivision_volume: f32,
+    /// Accent level of each beat in the measure, repeated if shorter than the measure
+    /// (empty to only accent the first beat)
+    pub beat_levels: Vec<AccentLevel>,
+    /// Sounds overriding the default sound of each accent level
+    pub level_sounds: LevelSounds,
 }
 
 impl Default for AccentConfig {
@@ -53,6 +144,8 @@ impl Default for AccentConfig {
             subdivision_duration: 80,      // Longer subdivision clicks for better audibility
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume: 0.7, // Higher volume for subdivisions
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 }
@@ -80,6 +173,8 @@ impl AccentConfig {
             subdivision_duration: 80,
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume: 0.7,
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -103,6 +198,8 @@ impl AccentConfig {
             subdivision_duration: 70, // Longer duration for better audibility
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume: 0.65, // Higher volume
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -121,6 +218,8 @@ impl AccentConfig {
             subdivision_duration: 80,
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume: 0.7,
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -139,6 +238,8 @@ impl AccentConfig {
             subdivision_duration: 80,
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume: 0.7,
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -157,6 +258,8 @@ impl AccentConfig {
             subdivision_duration: 80,
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume: 0.7,
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -175,6 +278,8 @@ impl AccentConfig {
             subdivision_duration: 80,
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume: 0.7,
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -193,6 +298,8 @@ impl AccentConfig {
             subdivision_duration: 80,
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume: 0.7,
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -211,6 +318,8 @@ impl AccentConfig {
             subdivision_duration: 80,
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume: 0.7,
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -231,6 +340,8 @@ impl AccentConfig {
             subdivision_duration: 70,
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume: 0.65, // Good volume for eighth notes
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -249,6 +360,8 @@ impl AccentConfig {
             subdivision_duration: 80,
             subdivision_wave_type: WaveType::Square, // Square wave is more cutting for fast subdivisions
             subdivision_volume: 0.55,                // Higher volume for sixteenth notes
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -267,6 +380,8 @@ impl AccentConfig {
             subdivision_duration: 65,
             subdivision_wave_type: WaveType::Triangle, // Different wave type for triplets
             subdivision_volume: 0.6,
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -289,6 +404,8 @@ impl AccentConfig {
             subdivision_duration: 70, // Better default duration
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume,
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -322,6 +439,159 @@ impl AccentConfig {
         self
     }
 
+    /// Returns a copy of this configuration with an accent level for each beat of the measure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use metronome_rs::{AccentConfig, AccentLevel};
+    ///
+    /// // Backbeat: accent beats 2 and 4
+    /// let backbeat = AccentConfig::default().set_beat_levels(&[
+    ///     AccentLevel::Weak,
+    ///     AccentLevel::Strong,
+    ///     AccentLevel::Weak,
+    ///     AccentLevel::Strong,
+    /// ]);
+    /// assert_eq!(backbeat.beat_level(1), Some(AccentLevel::Strong));
+    /// ```
+    #[must_use]
+    pub fn set_beat_levels(mut self, levels: &[AccentLevel]) -> Self {
+        self.beat_levels = levels.to_vec();
+        self
+    }
+
+    /// Returns a copy of this configuration that plays beats of the given level with `voice`.
+    ///
+    /// The voice's start frame is ignored.
+    #[must_use]
+    pub const fn set_level_sound(mut self, level: AccentLevel, voice: Voice) -> Self {
+        self.level_sounds.set(level, voice);
+        self
+    }
+
+    /// Gets the accent level of a beat, counted from the start of the measure,
+    /// or `None` if no beat levels are set.
+    #[must_use]
+    pub fn beat_level(&self, beat_in_measure: u64) -> Option<AccentLevel> {
+        let len = self.beat_levels.len() as u64;
+        if len == 0 {
+            return None;
+        }
+        usize::try_from(beat_in_measure % len)
+            .ok()
+            .map(|index| self.beat_levels[index])
+    }
+
+    /// Returns the voice played for beats of the given level, or `None` for muted beats.
+    #[must_use]
+    pub fn level_voice(&self, level: AccentLevel) -> Option<Voice> {
+        (level != AccentLevel::Mute).then(|| self.level_sound(level))
+    }
+
+    /// Returns the voice used for a click of the given kind.
+    ///
+    /// Accents, secondary accents and regular beats use the sounds of the strong,
+    /// medium and weak accent levels.
+    #[must_use]
+    pub fn voice_for(&self, kind: ClickKind) -> Voice {
+        match kind {
+            ClickKind::Accent => self.level_sound(AccentLevel::Strong),
+            ClickKind::SecondaryAccent => self.level_sound(AccentLevel::Medium),
+            ClickKind::Regular => self.level_sound(AccentLevel::Weak),
+            ClickKind::Subdivision => Voice::new(
+                self.subdivision_frequency,
+                self.subdivision_duration,
+                self.subdivision_wave_type.clone(),
+                self.subdivision_volume,
+            ),
+        }
+    }
+
+    /// Returns the voice played for a scheduled click, or `None` if its beat is muted.
+    ///
+    /// When beat levels are set, each main beat is played with the sound of its level;
+    /// otherwise the sound follows the click's kind.
+    #[must_use]
+    pub fn voice_for_click(
+        &self,
+        click: &ScheduledClick,
+        beats_per_measure: Option<u32>,
+    ) -> Option<Voice> {
+        if click.kind == ClickKind::Subdivision || click.subdivision != 0 {
+            return Some(self.voice_for(click.kind));
+        }
+
+        let beats = beats_per_measure.map_or(self.beat_levels.len() as u64, u64::from);
+        self.beat_level(click.beat % beats.max(1)).map_or_else(
+            || Some(self.voice_for(click.kind)),
+            |level| self.level_voice(level),
+        )
+    }
+
+    /// Returns the accent level of a scheduled click, or `None` for subdivisions.
+    ///
+    /// When beat levels are set, each main beat has the level set for it; otherwise
+    /// accents are strong, secondary accents medium and regular beats weak.
+    #[must_use]
+    pub fn level_for_click(
+        &self,
+        click: &ScheduledClick,
+        beats_per_measure: Option<u32>,
+    ) -> Option<AccentLevel> {
+        if click.kind == ClickKind::Subdivision || click.subdivision != 0 {
+            return None;
+        }
+
+        let beats = beats_per_measure.map_or(self.beat_levels.len() as u64, u64::from);
+        self.beat_level(click.beat % beats.max(1))
+            .or_else(|| AccentLevel::for_kind(click.kind))
+    }
+
+    /// Returns the voice for a level, using its default sound unless one is set.
+    fn level_sound(&self, level: AccentLevel) -> Voice {
+        if let Some(voice) = self.level_sounds.get(level) {
+            return Voice {
+                start_frame: None,
+                ..voice.clone()
+            };
+        }
+
+        match level {
+            AccentLevel::Strong => Voice::new(
+                self.accent_frequency,
+                self.accent_duration,
+                self.accent_wave_type.clone(),
+                1.0, // Full volume for accents
+            ),
+            // Secondary accents sit between the accent and regular clicks in pitch
+            AccentLevel::Medium => Voice::new(
+                (self.accent_frequency * self.regular_frequency).sqrt(),
+                self.regular_duration,
+                self.accent_wave_type.clone(),
+                1.0,
+            ),
+            AccentLevel::Weak => Voice::new(
+                self.regular_frequency,
+                self.regular_duration,
+                self.regular_wave_type.clone(),
+                1.0, // Full volume for main beats
+            ),
+            AccentLevel::Ghost => Voice::new(
+                self.regular_frequency,
+                self.regular_duration,
+                self.regular_wave_type.clone(),
+                GHOST_VOLUME,
+            ),
+            AccentLevel::Mute => Voice::new(
+                self.regular_frequency,
+                self.regular_duration,
+                self.regular_wave_type.clone(),
+                0.0,
+            ),
+        }
+    }
+
     // Extra subdivision presets for specific use cases
 
     /// Creates a configuration optimized for practicing slow pieces with clear subdivisions.
@@ -339,6 +609,8 @@ impl AccentConfig {
             subdivision_duration: 80,
             subdivision_wave_type: WaveType::Sine,
             subdivision_volume: 0.75, // High volume for practice
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 
@@ -357,6 +629,8 @@ impl AccentConfig {
             subdivision_duration: 70,
             subdivision_wave_type: WaveType::Square, // Very clear for fast passages
             subdivision_volume: 0.6,                 // Audible but not overwhelming
+            beat_levels: Vec::new(),
+            level_sounds: LevelSounds::new(),
         }
     }
 }
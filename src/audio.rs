@@ -3,6 +3,8 @@ use cpal::{
     traits::{DeviceTrait, HostTrait},
 };
 
+use crate::error::MetronomeError;
+
 /// Gets the default audio host.
 #[must_use]
 pub fn get_default_host() -> cpal::Host {
@@ -14,9 +16,9 @@ pub fn get_default_host() -> cpal::Host {
 /// # Errors
 ///
 /// Returns an error if no output device is available on the host.
-pub fn get_default_output_device(host: &cpal::Host) -> Result<Device, Box<dyn std::error::Error>> {
+pub fn get_default_output_device(host: &cpal::Host) -> Result<Device, MetronomeError> {
     host.default_output_device()
-        .ok_or_else(|| "no output device available".into())
+        .ok_or(MetronomeError::NoOutputDevice)
 }
 
 /// Gets the default output configuration for the given device.
@@ -24,10 +26,6 @@ pub fn get_default_output_device(host: &cpal::Host) -> Result<Device, Box<dyn st
 /// # Errors
 ///
 /// Returns an error if the default output configuration cannot be retrieved from the device.
-pub fn get_default_output_config(
-    device: &Device,
-) -> Result<SupportedStreamConfig, Box<dyn std::error::Error>> {
-    device
-        .default_output_config()
-        .map_err(std::convert::Into::into)
+pub fn get_default_output_config(device: &Device) -> Result<SupportedStreamConfig, MetronomeError> {
+    device.default_output_config().map_err(MetronomeError::from)
 }
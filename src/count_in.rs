@@ -0,0 +1,141 @@
+use crate::accent::AccentConfig;
+use crate::error::MetronomeError;
+use crate::schedule::{ClickScheduler, ScheduledClick};
+use crate::voice::Voice;
+
+/// What a count-in sounds like.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum CountInSound {
+    /// The metronome's own accent and regular beat sounds
+    #[default]
+    Clicks,
+    /// One voice for every count-in beat, so the count-in stands apart from the main click
+    Voice(Voice),
+    /// A voice for each beat of the bar, in order, such as a different pitch for every count
+    Counted(Vec<Voice>),
+}
+
+/// Bars played before a metronome's main click starts, to count the player in.
+///
+/// The count-in clicks every beat of the bar at the starting tempo, without
+/// subdivisions. Bars and beats of the main click are still counted from 0 after it,
+/// and [`BeatEvent::count_in`](crate::BeatEvent::count_in) marks the count-in's events.
+///
+/// The crate synthesizes its sounds, so a spoken count isn't available; a
+/// [`CountInSound::Counted`] count-in gives every count its own sound instead.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, CountIn, Metronome, Voice, WaveType};
+///
+/// let mut metronome = Metronome::offline(100.0, Some(4), AccentConfig::default())?;
+///
+/// // Two bars of woodblock-like clicks before the main click
+/// let woodblock = Voice::new(1200.0, 40, WaveType::Square, 0.6);
+/// metronome.set_count_in(Some(CountIn::bars(2).with_voice(woodblock)));
+/// assert_eq!(metronome.count_in().map(|count_in| count_in.bars), Some(2));
+/// # Ok::<(), metronome_rs::MetronomeError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CountIn {
+    /// Number of bars counted in (beats when the metronome has no measures)
+    pub bars: u32,
+    /// Sound of the count-in clicks
+    pub sound: CountInSound,
+}
+
+impl CountIn {
+    /// Creates a count-in of `bars` bars using the metronome's own sounds.
+    #[must_use]
+    pub const fn bars(bars: u32) -> Self {
+        Self {
+            bars,
+            sound: CountInSound::Clicks,
+        }
+    }
+
+    /// Returns a copy of this count-in that plays `voice` on every beat.
+    #[must_use]
+    pub fn with_voice(mut self, voice: Voice) -> Self {
+        self.sound = CountInSound::Voice(voice);
+        self
+    }
+
+    /// Returns a copy of this count-in that plays `voices[n]` on beat `n` of each bar,
+    /// repeating them when the bar has more beats than voices.
+    #[must_use]
+    pub fn with_counted_voices(mut self, voices: Vec<Voice>) -> Self {
+        self.sound = CountInSound::Counted(voices);
+        self
+    }
+
+    /// Returns the voice played for a count-in click.
+    pub(crate) fn voice_for(
+        &self,
+        click: &ScheduledClick,
+        beats_per_measure: Option<u32>,
+        accent_config: &AccentConfig,
+    ) -> Voice {
+        match &self.sound {
+            CountInSound::Clicks => accent_config.voice_for(click.kind),
+            CountInSound::Voice(voice) => voice.clone(),
+            CountInSound::Counted(voices) => {
+                let beats = u64::from(beats_per_measure.unwrap_or(1).max(1));
+                let count = usize::try_from(click.beat % beats).unwrap_or_default();
+                voices[count % voices.len()].clone()
+            }
+        }
+    }
+
+    /// Checks that the count-in can be played.
+    pub(crate) fn validate(&self) -> Result<(), MetronomeError> {
+        match &self.sound {
+            CountInSound::Counted(voices) if voices.is_empty() => {
+                Err(MetronomeError::InvalidConfig(
+                    "a counted count-in needs at least one voice".to_string(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The clicks of a count-in, placed before the main click's scheduler.
+#[derive(Clone, Debug)]
+pub(crate) struct CountInClicks {
+    scheduler: ClickScheduler,
+    beats: u64,
+}
+
+impl CountInClicks {
+    /// Creates the clicks of a count-in starting at `first_frame`, at the tempo and
+    /// measure of the main click's scheduler.
+    pub(crate) fn new(count_in: &CountIn, main: &ClickScheduler, first_frame: u64) -> Self {
+        let beats_per_measure = main.beats_per_measure();
+        Self {
+            scheduler: ClickScheduler::new(main.bpm(), beats_per_measure, 1, main.sample_rate())
+                .starting_at(first_frame),
+            beats: u64::from(count_in.bars) * u64::from(beats_per_measure.unwrap_or(1).max(1)),
+        }
+    }
+
+    /// Gets the frame the main click starts on, right after the count-in.
+    pub(crate) fn end_frame(&self) -> u64 {
+        self.scheduler
+            .clone()
+            .find(|click| click.beat >= self.beats)
+            .map_or(0, |click| click.frame)
+    }
+
+    /// Returns the next count-in click if it starts before `limit`.
+    pub(crate) fn next_before(&mut self, limit: u64) -> Option<ScheduledClick> {
+        let mut peek = self.scheduler.clone();
+        let click = peek.next_click();
+        if click.beat >= self.beats || click.frame >= limit {
+            return None;
+        }
+        self.scheduler = peek;
+        Some(click)
+    }
+}
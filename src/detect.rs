@@ -0,0 +1,548 @@
+//! Tempo detection for recordings, so a metronome can be started in time with them.
+//!
+//! Detection is offline and deterministic: the same audio always gives the same
+//! estimate. It looks for a steady pulse of onsets, so it works best on recordings
+//! with clear attacks such as drums or click tracks.
+
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::error::MetronomeError;
+
+/// Frames per second of the onset envelope the pulse is searched in
+const ENVELOPE_RATE: u32 = 100;
+
+/// Slowest tempo that can be detected
+const MIN_BPM: f64 = 40.0;
+
+/// Fastest tempo that can be detected
+const MAX_BPM: f64 = 240.0;
+
+/// Share of the strongest pulse's correlation a faster pulse needs to be preferred
+const FASTER_PULSE_SHARE: f64 = 0.95;
+
+/// Largest ratio between the strongest pulse and a faster pulse preferred over it
+const MAX_PULSE_DIVISOR: usize = 8;
+
+/// Largest distance of an onset from the beat grid, in beats, for it to count as on the beat
+const ON_BEAT_TOLERANCE: f64 = 0.125;
+
+/// Length of the window each beat's sound is measured in, in milliseconds
+const BEAT_WINDOW_MS: u32 = 30;
+
+/// Most beats per measure considered when looking for accents
+const MAX_BEATS_PER_MEASURE: usize = 7;
+
+/// Smallest relative difference between accented and other beats for a meter to be detected
+const MIN_ACCENT_CONTRAST: f64 = 0.25;
+
+/// The tempo and beat positions found in a recording.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TempoEstimate {
+    /// Estimated tempo
+    pub bpm: f64,
+    /// Time of the first beat from the start of the recording
+    pub first_beat: Duration,
+    /// Time of the first downbeat from the start of the recording, or of the first beat
+    /// when no measures were found
+    pub first_downbeat: Duration,
+    /// Number of beats between accented beats, if the recording has audible accents
+    pub beats_per_measure: Option<u32>,
+}
+
+impl TempoEstimate {
+    /// Gets the length of one beat, or `None` if `bpm` isn't a positive number.
+    #[must_use]
+    pub fn beat_duration(&self) -> Option<Duration> {
+        Duration::try_from_secs_f64(60.0 / self.bpm)
+            .ok()
+            .filter(|beat| !beat.is_zero())
+    }
+
+    /// Gets the time of the first downbeat at or after `position` in the recording.
+    ///
+    /// Starting a metronome with the same tempo and measure this long after the
+    /// recording starts playing lines its accents up with the recording's downbeats.
+    ///
+    /// Returns `None` if the estimate has no playable beat, such as a tempo that isn't
+    /// positive or a measure of no beats, or if the downbeat is too far away to be held
+    /// in a [`Duration`].
+    #[must_use]
+    pub fn downbeat_after(&self, position: Duration) -> Option<Duration> {
+        let Some(since_first) = position.checked_sub(self.first_downbeat) else {
+            return Some(self.first_downbeat);
+        };
+        let bar = self
+            .beat_duration()?
+            .checked_mul(self.beats_per_measure.unwrap_or(1))
+            .filter(|bar| !bar.is_zero())?;
+        let bars = (since_first.as_secs_f64() / bar.as_secs_f64()).ceil();
+        let offset = Duration::try_from_secs_f64(bar.as_secs_f64() * bars).ok()?;
+        self.first_downbeat.checked_add(offset)
+    }
+}
+
+/// Estimates the tempo of interleaved `f32` samples, such as those returned by
+/// [`render_samples`](crate::render_samples).
+///
+/// Returns `None` if no steady pulse between 40 and 240 BPM is found, or if the sample
+/// rate is 0.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, Metronome, RenderLength, detect_tempo, render_samples};
+///
+/// let metronome = Metronome::offline(90.0, Some(4), AccentConfig::default())?;
+/// let samples = render_samples(&metronome, RenderLength::Bars(4), 48000, 1)?;
+///
+/// let estimate = detect_tempo(&samples, 48000, 1).unwrap();
+/// assert!((estimate.bpm - 90.0).abs() < 0.1);
+/// assert_eq!(estimate.beats_per_measure, Some(4));
+/// # Ok::<(), metronome_rs::MetronomeError>(())
+/// ```
+#[must_use]
+pub fn detect_tempo(samples: &[f32], sample_rate: u32, channels: u16) -> Option<TempoEstimate> {
+    if sample_rate == 0 {
+        return None;
+    }
+    let mono = mixdown(samples, channels);
+    let hop = usize::try_from(sample_rate / ENVELOPE_RATE).ok()?.max(1);
+    let envelope = onset_envelope(&mono, hop);
+    let period = beat_period(&envelope)?;
+    let onsets = find_onsets(&mono, &envelope, hop, period);
+    #[allow(clippy::cast_precision_loss)]
+    let grid = fit_grid(&onsets, (period * hop) as f64)?;
+
+    let window = usize::try_from(sample_rate.checked_mul(BEAT_WINDOW_MS)? / 1000).ok()?;
+    let features: Vec<[f64; 2]> = (grid.first..=grid.last)
+        .map(|beat| beat_features(&mono, grid.position(beat), window))
+        .collect();
+    let meter = find_meter(&features);
+    let downbeat = meter.map_or(grid.first, |(_, phase)| grid.first + phase);
+
+    let seconds = |position: f64| {
+        Duration::try_from_secs_f64(position.max(0.0) / f64::from(sample_rate)).ok()
+    };
+    Some(TempoEstimate {
+        bpm: 60.0 * f64::from(sample_rate) / grid.period,
+        first_beat: seconds(grid.position(grid.first))?,
+        first_downbeat: seconds(grid.position(downbeat))?,
+        beats_per_measure: meter.and_then(|(beats, _)| u32::try_from(beats).ok()),
+    })
+}
+
+/// Estimates the tempo of a WAV stream.
+///
+/// Integer PCM with 8, 16, 24 or 32 bits and 32 or 64-bit float data are supported,
+/// with any number of channels.
+///
+/// # Errors
+///
+/// Returns [`MetronomeError::Io`] if reading fails or the stream isn't a supported WAV file.
+pub fn detect_tempo_from_wav<R: Read>(
+    mut reader: R,
+) -> Result<Option<TempoEstimate>, MetronomeError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let (samples, sample_rate, channels) = read_wav(&bytes)?;
+    Ok(detect_tempo(&samples, sample_rate, channels))
+}
+
+/// Estimates the tempo of a WAV file.
+///
+/// # Examples
+///
+/// ```no_run
+/// use metronome_rs::{Metronome, detect_tempo_from_wav_file};
+/// use std::time::Duration;
+///
+/// if let Some(estimate) = detect_tempo_from_wav_file("song.wav")? {
+///     println!("{:.1} BPM, first downbeat at {:?}", estimate.bpm, estimate.first_downbeat);
+///     let metronome = Metronome::new(estimate.bpm, estimate.beats_per_measure)?;
+///     // Start the metronome when the recording reaches estimate.first_downbeat
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`MetronomeError::Io`] if the file cannot be read or isn't a supported WAV file.
+pub fn detect_tempo_from_wav_file(
+    path: impl AsRef<Path>,
+) -> Result<Option<TempoEstimate>, MetronomeError> {
+    detect_tempo_from_wav(BufReader::new(File::open(path)?))
+}
+
+/// A steady beat grid fitted to onsets, in samples.
+struct BeatGrid {
+    /// Position of beat 0
+    origin: f64,
+    /// Samples per beat
+    period: f64,
+    /// First beat with an onset
+    first: i64,
+    /// Last beat with an onset
+    last: i64,
+}
+
+impl BeatGrid {
+    #[allow(clippy::cast_precision_loss)]
+    const fn position(&self, beat: i64) -> f64 {
+        (beat as f64).mul_add(self.period, self.origin)
+    }
+}
+
+/// Averages interleaved channels into one.
+fn mixdown(samples: &[f32], channels: u16) -> Vec<f32> {
+    let channels = channels.max(1);
+    samples
+        .chunks_exact(usize::from(channels))
+        .map(|frame| frame.iter().sum::<f32>() / f32::from(channels))
+        .collect()
+}
+
+/// Computes how much the energy rises from one block of `hop` samples to the next.
+fn onset_envelope(mono: &[f32], hop: usize) -> Vec<f64> {
+    // The recording starts from silence, so a sound in the first block is an onset too
+    let mut previous = 0.0;
+    mono.chunks(hop)
+        .map(|block| {
+            let energy: f64 = block.iter().map(|&x| f64::from(x) * f64::from(x)).sum();
+            #[allow(clippy::cast_precision_loss)]
+            let level = energy / block.len() as f64;
+            let rise = (level - previous).max(0.0);
+            previous = level;
+            rise
+        })
+        .collect()
+}
+
+/// Finds the beat period in envelope frames from the envelope's autocorrelation.
+fn beat_period(envelope: &[f64]) -> Option<usize> {
+    let rate = f64::from(ENVELOPE_RATE);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let min_lag = (60.0 * rate / MAX_BPM).floor() as usize;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let max_lag = ((60.0 * rate / MIN_BPM).ceil() as usize).min(envelope.len() / 2);
+    if max_lag <= min_lag {
+        return None;
+    }
+
+    // Onsets can straddle two frames, so neighbouring lags are counted too
+    let score = |lag: usize| {
+        (lag - 1..=lag + 1)
+            .map(|lag| autocorrelation(envelope, lag))
+            .sum::<f64>()
+    };
+
+    let mut best = min_lag;
+    for lag in min_lag..=max_lag {
+        if score(lag) > score(best) {
+            best = lag;
+        }
+    }
+    if score(best) <= 0.0 {
+        return None;
+    }
+
+    // Every multiple of the beat correlates too, so prefer the fastest pulse that is about as strong
+    let threshold = FASTER_PULSE_SHARE * score(best);
+    let fastest = (2..=MAX_PULSE_DIVISOR)
+        .filter_map(|divisor| {
+            [best / divisor, best.div_ceil(divisor)]
+                .into_iter()
+                .filter(|&lag| lag >= min_lag)
+                .max_by(|a, b| score(*a).total_cmp(&score(*b)))
+        })
+        .filter(|&lag| score(lag) >= threshold)
+        .min();
+    Some(fastest.unwrap_or(best))
+}
+
+/// Computes the autocorrelation of the envelope at a lag, normalized by the overlap.
+fn autocorrelation(envelope: &[f64], lag: usize) -> f64 {
+    let overlap = envelope.len().saturating_sub(lag);
+    if overlap == 0 {
+        return 0.0;
+    }
+    let sum: f64 = envelope
+        .iter()
+        .zip(&envelope[lag..])
+        .map(|(a, b)| a * b)
+        .sum();
+    #[allow(clippy::cast_precision_loss)]
+    let overlap = overlap as f64;
+    sum / overlap
+}
+
+/// Finds onsets as peaks of the envelope, returning their sample positions and strengths.
+fn find_onsets(mono: &[f32], envelope: &[f64], hop: usize, period: usize) -> Vec<(f64, f64)> {
+    let strongest = envelope.iter().copied().fold(0.0, f64::max);
+    let threshold = strongest * 0.1;
+    // Peaks closer than a quarter beat belong to the same onset
+    let reach = (period / 4).max(1);
+
+    let mut onsets = Vec::new();
+    for (index, &strength) in envelope.iter().enumerate() {
+        let before = &envelope[index.saturating_sub(reach)..index];
+        let after = &envelope[index + 1..(index + 1 + reach).min(envelope.len())];
+        if strength <= threshold
+            || before.iter().any(|&other| other >= strength)
+            || after.iter().any(|&other| other > strength)
+        {
+            continue;
+        }
+
+        // The sound starts in this block or the one before; find its first loud sample
+        let start = index.saturating_sub(1) * hop;
+        let end = ((index + 1) * hop).min(mono.len());
+        let block = &mono[start..end];
+        let peak = block.iter().fold(0.0_f32, |peak, x| peak.max(x.abs()));
+        let offset = block
+            .iter()
+            .position(|x| x.abs() >= peak * 0.1)
+            .unwrap_or_default();
+        #[allow(clippy::cast_precision_loss)]
+        onsets.push(((start + offset) as f64, strength));
+    }
+    onsets
+}
+
+/// Fits a steady beat grid to the onsets, starting from an approximate period in samples.
+fn fit_grid(onsets: &[(f64, f64)], period: f64) -> Option<BeatGrid> {
+    let strongest = onsets
+        .iter()
+        .map(|&(_, strength)| strength)
+        .fold(0.0, f64::max);
+    // Weak onsets may be subdivisions, so the grid starts on a strong one
+    let (origin, _) = *onsets
+        .iter()
+        .find(|&&(_, strength)| strength >= strongest * 0.6)?;
+
+    let mut grid = BeatGrid {
+        origin,
+        period,
+        first: 0,
+        last: 0,
+    };
+    let span = onsets
+        .last()
+        .map_or(0.0, |&(last, _)| (last - onsets[0].0) / period);
+    // The approximate period drifts off the beats over a long recording, so each pass
+    // fits beats twice as far from the origin, until the whole recording is covered
+    // and no more beats are added
+    let mut reach = 4.0;
+    let mut fitted = 0;
+    loop {
+        let beats: Vec<(f64, f64)> = onsets
+            .iter()
+            .filter_map(|&(position, _)| {
+                let beat = ((position - grid.origin) / grid.period).round();
+                let distance = (position - grid.origin) / grid.period - beat;
+                (distance.abs() < ON_BEAT_TOLERANCE && beat.abs() <= reach)
+                    .then_some((beat, position))
+            })
+            .collect();
+        if beats.len() < 2 || (reach > span && beats.len() <= fitted) {
+            break;
+        }
+        fitted = beats.len();
+        reach *= 2.0;
+
+        // Least squares line through (beat, position)
+        #[allow(clippy::cast_precision_loss)]
+        let count = beats.len() as f64;
+        let mean_beat = beats.iter().map(|&(beat, _)| beat).sum::<f64>() / count;
+        let mean_position = beats.iter().map(|&(_, position)| position).sum::<f64>() / count;
+        let (covariance, variance) =
+            beats
+                .iter()
+                .fold((0.0, 0.0), |(covariance, variance), &(beat, position)| {
+                    let beat = beat - mean_beat;
+                    (
+                        beat.mul_add(position - mean_position, covariance),
+                        beat.mul_add(beat, variance),
+                    )
+                });
+        if variance <= 0.0 {
+            return None;
+        }
+        grid.period = covariance / variance;
+        grid.origin = grid.period.mul_add(-mean_beat, mean_position);
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            grid.first = beats.iter().map(|&(beat, _)| beat as i64).min()?;
+            grid.last = beats.iter().map(|&(beat, _)| beat as i64).max()?;
+        }
+    }
+
+    (fitted >= 2 && grid.period > 0.0).then_some(grid)
+}
+
+/// Measures the loudness and brightness (zero crossings) of a beat starting at a position.
+fn beat_features(mono: &[f32], position: f64, window: usize) -> [f64; 2] {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let start = (position.max(0.0).round() as usize).min(mono.len());
+    let block = &mono[start..(start + window).min(mono.len())];
+    let loudness = block.iter().fold(0.0_f32, |peak, x| peak.max(x.abs()));
+    let crossings = block
+        .windows(2)
+        .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+        .count();
+    #[allow(clippy::cast_precision_loss)]
+    let crossings = crossings as f64;
+    [f64::from(loudness), crossings]
+}
+
+/// Finds the number of beats per measure and the index of the first downbeat from the
+/// beats that sound different from the others.
+fn find_meter(features: &[[f64; 2]]) -> Option<(usize, i64)> {
+    let mut best: Option<(f64, usize, usize)> = None;
+    for beats in 2..=MAX_BEATS_PER_MEASURE {
+        // Accents need to repeat to be told apart from chance
+        if features.len() < beats * 2 {
+            break;
+        }
+        for phase in 0..beats {
+            let contrast = accent_contrast(features, beats, phase);
+            if best.is_none_or(|(best, _, _)| contrast > best) {
+                best = Some((contrast, beats, phase));
+            }
+        }
+    }
+
+    let (contrast, beats, phase) = best?;
+    (contrast >= MIN_ACCENT_CONTRAST).then(|| (beats, i64::try_from(phase).unwrap_or_default()))
+}
+
+/// Measures how much the beats at `phase` of every group of `beats` differ from the
+/// other beats, relative to the average beat.
+fn accent_contrast(features: &[[f64; 2]], beats: usize, phase: usize) -> f64 {
+    (0..2)
+        .map(|feature| {
+            let (mut accented, mut accented_count) = (0.0, 0_u32);
+            let (mut others, mut others_count) = (0.0, 0_u32);
+            for (index, values) in features.iter().enumerate() {
+                if index % beats == phase {
+                    accented += values[feature];
+                    accented_count += 1;
+                } else {
+                    others += values[feature];
+                    others_count += 1;
+                }
+            }
+            let average = (accented + others) / f64::from(accented_count + others_count);
+            if average <= 0.0 || others_count == 0 {
+                return 0.0;
+            }
+            let accented = accented / f64::from(accented_count);
+            let others = others / f64::from(others_count);
+            (accented - others).abs() / average
+        })
+        .sum()
+}
+
+/// Decodes a WAV file into interleaved `f32` samples, its sample rate and channel count.
+fn read_wav(bytes: &[u8]) -> Result<(Vec<f32>, u32, u16), MetronomeError> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(invalid_wav("missing RIFF/WAVE header"));
+    }
+
+    let mut format = None;
+    let mut position = 12;
+    while position + 8 <= bytes.len() {
+        let id = &bytes[position..position + 4];
+        let len = usize::try_from(read_u32(bytes, position + 4))
+            .map_err(|_| invalid_wav("chunk too large"))?;
+        let body_start = position + 8;
+        // Writers that stream their output may leave the data length unfinished
+        let body = &bytes[body_start..body_start.saturating_add(len).min(bytes.len())];
+
+        match id {
+            b"fmt " => format = Some(WavFormat::parse(body)?),
+            b"data" => {
+                let format = format.ok_or_else(|| invalid_wav("data chunk before fmt chunk"))?;
+                return Ok((format.decode(body)?, format.sample_rate, format.channels));
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even length
+        position = body_start.saturating_add(len).saturating_add(len & 1);
+    }
+    Err(invalid_wav("missing data chunk"))
+}
+
+/// The sample format of a WAV file.
+#[derive(Clone, Copy)]
+struct WavFormat {
+    /// 1 for integer PCM, 3 for floating point
+    format_tag: u16,
+    channels: u16,
+    sample_rate: u32,
+    bits: u16,
+}
+
+impl WavFormat {
+    fn parse(body: &[u8]) -> Result<Self, MetronomeError> {
+        if body.len() < 16 {
+            return Err(invalid_wav("fmt chunk too short"));
+        }
+        let mut format_tag = read_u16(body, 0);
+        // WAVE_FORMAT_EXTENSIBLE keeps the actual format at the start of its sub-format GUID
+        if format_tag == 0xFFFE && body.len() >= 26 {
+            format_tag = read_u16(body, 24);
+        }
+        let format = Self {
+            format_tag,
+            channels: read_u16(body, 2),
+            sample_rate: read_u32(body, 4),
+            bits: read_u16(body, 14),
+        };
+        if format.channels == 0 || format.sample_rate == 0 {
+            return Err(invalid_wav("no channels or no sample rate"));
+        }
+        Ok(format)
+    }
+
+    fn decode(self, data: &[u8]) -> Result<Vec<f32>, MetronomeError> {
+        let bytes = usize::from(self.bits / 8);
+        let decode: fn(&[u8]) -> f32 = match (self.format_tag, self.bits) {
+            (1, 8) => |b| (f32::from(b[0]) - 128.0) / 128.0,
+            (1, 16) => |b| f32::from(i16::from_le_bytes([b[0], b[1]])) / 32_768.0,
+            #[allow(clippy::cast_precision_loss)]
+            (1, 24) => |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0,
+            #[allow(clippy::cast_precision_loss)]
+            (1, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0,
+            (3, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            #[allow(clippy::cast_possible_truncation)]
+            (3, 64) => {
+                |b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32
+            }
+            (tag, bits) => {
+                return Err(invalid_wav(&format!(
+                    "unsupported sample format {tag} with {bits} bits"
+                )));
+            }
+        };
+        Ok(data.chunks_exact(bytes).map(decode).collect())
+    }
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn invalid_wav(message: &str) -> MetronomeError {
+    std::io::Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid WAV file: {message}"),
+    )
+    .into()
+}
@@ -0,0 +1,258 @@
+use cpal::{
+    Device, FromSample, SampleFormat, SizedSample, StreamConfig,
+    traits::{DeviceTrait, StreamTrait},
+};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU64, Ordering},
+    mpsc,
+};
+use std::thread;
+use std::time::Duration;
+
+use crate::audio::{get_default_host, get_default_output_config, get_default_output_device};
+use crate::error::MetronomeError;
+use crate::voice::{Voice, VoiceMixer};
+
+/// Engines that are currently open, shared by every caller using the same device and configuration
+static SHARED_ENGINES: Mutex<Vec<Arc<AudioEngine>>> = Mutex::new(Vec::new());
+
+/// Identifies the device and stream configuration an engine was opened with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct EngineKey {
+    device_name: String,
+    sample_rate: u32,
+    channels: u16,
+    sample_format: SampleFormat,
+}
+
+impl EngineKey {
+    fn new<T: SizedSample>(device: &Device, config: &StreamConfig) -> Self {
+        Self {
+            device_name: device.name().unwrap_or_default(),
+            sample_rate: config.sample_rate.0,
+            channels: config.channels,
+            sample_format: T::FORMAT,
+        }
+    }
+}
+
+/// A long-lived audio engine that owns a single output stream on a device.
+///
+/// Sounds are submitted to the engine as [`Voice`]s and mixed into the running
+/// stream, so playing a click never has to open or tear down an output stream.
+/// Engines are normally obtained through [`AudioEngine::shared`] or
+/// [`AudioEngine::for_device`], which reuse one engine per device and configuration.
+pub struct AudioEngine {
+    key: EngineKey,
+    sample_rate: u32,
+    channels: u16,
+    frames_played: Arc<AtomicU64>,
+    /// Time between rendering a buffer and it reaching the speakers, in nanoseconds
+    latency_nanos: Arc<AtomicU64>,
+    voices: mpsc::Sender<Voice>,
+    // Dropping this sender shuts down the thread that owns the stream
+    _shutdown: mpsc::Sender<()>,
+}
+
+impl AudioEngine {
+    /// Opens a new engine with its own output stream on the given device.
+    ///
+    /// # Type Parameters
+    ///
+    /// * `T` - The sample type (e.g., f32, i16, u16) used by the output stream
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output stream cannot be built or started.
+    pub fn open<T>(device: &Device, config: &StreamConfig) -> Result<Arc<Self>, MetronomeError>
+    where
+        T: SizedSample + FromSample<f32>,
+    {
+        let key = EngineKey::new::<T>(device, config);
+        let sample_rate = config.sample_rate.0;
+        let channels = config.channels;
+        let frames_played = Arc::new(AtomicU64::new(0));
+        let latency_nanos = Arc::new(AtomicU64::new(0));
+        let (voice_tx, voice_rx) = mpsc::channel::<Voice>();
+        let (shutdown_tx, shutdown_rx) = mpsc::channel::<()>();
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<(), MetronomeError>>();
+
+        // The stream lives on its own thread because cpal streams are not `Send` on every platform
+        let device = device.clone();
+        let config = config.clone();
+        let clock = Arc::clone(&frames_played);
+        let latency = Arc::clone(&latency_nanos);
+        thread::spawn(move || {
+            let stream = match build_stream::<T>(&device, &config, voice_rx, clock, latency) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+
+            // Keep the stream alive until the engine is dropped
+            let _ = shutdown_rx.recv();
+            drop(stream);
+        });
+
+        ready_rx.recv().map_err(|_| {
+            MetronomeError::BuildStream(cpal::BuildStreamError::BackendSpecific {
+                err: cpal::BackendSpecificError {
+                    description: "audio engine thread exited unexpectedly".to_string(),
+                },
+            })
+        })??;
+
+        Ok(Arc::new(Self {
+            key,
+            sample_rate,
+            channels,
+            frames_played,
+            latency_nanos,
+            voices: voice_tx,
+            _shutdown: shutdown_tx,
+        }))
+    }
+
+    /// Returns the shared engine for the given device and configuration, opening it if necessary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a new engine has to be opened and its output stream cannot be started,
+    /// or [`MetronomeError::Poisoned`] if another thread panicked while opening an engine.
+    pub fn shared<T>(device: &Device, config: &StreamConfig) -> Result<Arc<Self>, MetronomeError>
+    where
+        T: SizedSample + FromSample<f32>,
+    {
+        let key = EngineKey::new::<T>(device, config);
+
+        let mut engines = SHARED_ENGINES.lock()?;
+        if let Some(engine) = engines.iter().find(|engine| engine.key == key) {
+            return Ok(Arc::clone(engine));
+        }
+
+        // Keep the lock while opening so two callers can't open the same device twice
+        let engine = Self::open::<T>(device, config)?;
+        engines.push(Arc::clone(&engine));
+        drop(engines);
+
+        Ok(engine)
+    }
+
+    /// Returns the shared engine for the given device, automatically handling sample format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device configuration cannot be obtained or if the engine cannot be opened.
+    /// Devices using a sample format other than f32, i16 or u16 are tried as f32, and
+    /// [`MetronomeError::UnsupportedSampleFormat`] is returned if that fails too.
+    pub fn for_device(device: &Device, config: &StreamConfig) -> Result<Arc<Self>, MetronomeError> {
+        match device.default_output_config()?.sample_format() {
+            SampleFormat::F32 => Self::shared::<f32>(device, config),
+            SampleFormat::I16 => Self::shared::<i16>(device, config),
+            SampleFormat::U16 => Self::shared::<u16>(device, config),
+            sample_format => {
+                println!("Unsupported sample format '{sample_format}', trying f32");
+                Self::shared::<f32>(device, config)
+                    .map_err(|_| MetronomeError::UnsupportedSampleFormat(sample_format))
+            }
+        }
+    }
+
+    /// Returns the shared engine for the default output device and configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the default device or configuration cannot be obtained, or if the engine cannot be opened.
+    pub fn default_output() -> Result<Arc<Self>, MetronomeError> {
+        let host = get_default_host();
+        let device = get_default_output_device(&host)?;
+        let config = get_default_output_config(&device)?;
+
+        Self::for_device(&device, &config.into())
+    }
+
+    /// Submits a voice to be mixed into the output stream.
+    pub fn play(&self, voice: Voice) {
+        // Sending only fails once the stream thread has gone away, in which case there is nothing to play on
+        let _ = self.voices.send(voice);
+    }
+
+    /// Gets the sample rate of the output stream.
+    #[must_use]
+    pub const fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Gets the number of channels of the output stream.
+    #[must_use]
+    pub const fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Gets the number of frames the output stream has rendered so far.
+    ///
+    /// This is the engine clock that [`Voice::start_frame`] refers to.
+    #[must_use]
+    pub fn frames_played(&self) -> u64 {
+        self.frames_played.load(Ordering::Acquire)
+    }
+
+    /// Gets the time between the engine rendering a frame and it being played by the device,
+    /// as last reported by the output stream.
+    #[must_use]
+    pub fn output_latency(&self) -> Duration {
+        Duration::from_nanos(self.latency_nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// Builds and starts the output stream that mixes submitted voices.
+fn build_stream<T>(
+    device: &Device,
+    config: &StreamConfig,
+    voices: mpsc::Receiver<Voice>,
+    frames_played: Arc<AtomicU64>,
+    latency_nanos: Arc<AtomicU64>,
+) -> Result<cpal::Stream, MetronomeError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    let mut mixer = VoiceMixer::real_time(config.sample_rate.0);
+    // Room for a second of frames, so the callback doesn't allocate for common buffer sizes
+    let mut buffer = Vec::with_capacity(config.sample_rate.0 as usize);
+
+    let err_fn = |err| eprintln!("an error occurred on stream: {err}");
+
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+            let timestamp = info.timestamp();
+            if let Some(latency) = timestamp.playback.duration_since(&timestamp.callback) {
+                let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+                latency_nanos.store(nanos, Ordering::Relaxed);
+            }
+
+            let now = frames_played.load(Ordering::Acquire);
+            while let Ok(voice) = voices.try_recv() {
+                mixer.add(voice, now);
+            }
+
+            buffer.resize(data.len() / channels, 0.0);
+            mixer.render(&mut buffer, now);
+
+            for (frame, value) in data.chunks_mut(channels).zip(&buffer) {
+                frame.fill(T::from_sample(*value));
+            }
+            frames_played.store(now + buffer.len() as u64, Ordering::Release);
+        },
+        err_fn,
+        None,
+    )?;
+    stream.play()?;
+
+    Ok(stream)
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+use cpal::SampleFormat;
+
+/// Errors returned by the metronome, tone and audio functions.
+///
+/// Every failure has its own variant, so callers can react to specific cases
+/// (for example, asking the user to connect headphones when there is no output device).
+#[derive(Debug)]
+pub enum MetronomeError {
+    /// No audio output device is available
+    NoOutputDevice,
+    /// The output device uses a sample format the metronome can't play
+    UnsupportedSampleFormat(SampleFormat),
+    /// The output configuration of the device couldn't be retrieved
+    StreamConfig(cpal::DefaultStreamConfigError),
+    /// The output stream couldn't be built
+    BuildStream(cpal::BuildStreamError),
+    /// The output stream was built but couldn't be started
+    PlayStream(cpal::PlayStreamError),
+    /// A setting is out of range, such as a tempo that isn't positive
+    InvalidConfig(String),
+    /// Shared state was poisoned by a panic in another thread
+    Poisoned,
+    /// Reading or writing a file or stream failed
+    Io(std::io::Error),
+    /// The metronome was stopped or started again before it finished playing
+    Stopped,
+}
+
+impl fmt::Display for MetronomeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoOutputDevice => write!(f, "no output device available"),
+            Self::UnsupportedSampleFormat(format) => {
+                write!(f, "unsupported sample format '{format}'")
+            }
+            Self::StreamConfig(e) => write!(f, "failed to get output configuration: {e}"),
+            Self::BuildStream(e) => write!(f, "failed to build output stream: {e}"),
+            Self::PlayStream(e) => write!(f, "failed to start output stream: {e}"),
+            Self::InvalidConfig(message) => write!(f, "invalid configuration: {message}"),
+            Self::Poisoned => write!(f, "shared state poisoned by a panic in another thread"),
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Stopped => write!(f, "metronome stopped before it finished"),
+        }
+    }
+}
+
+impl std::error::Error for MetronomeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::StreamConfig(e) => Some(e),
+            Self::BuildStream(e) => Some(e),
+            Self::PlayStream(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::NoOutputDevice
+            | Self::UnsupportedSampleFormat(_)
+            | Self::InvalidConfig(_)
+            | Self::Poisoned
+            | Self::Stopped => None,
+        }
+    }
+}
+
+impl From<cpal::DefaultStreamConfigError> for MetronomeError {
+    fn from(e: cpal::DefaultStreamConfigError) -> Self {
+        match e {
+            cpal::DefaultStreamConfigError::DeviceNotAvailable => Self::NoOutputDevice,
+            e => Self::StreamConfig(e),
+        }
+    }
+}
+
+impl From<cpal::BuildStreamError> for MetronomeError {
+    fn from(e: cpal::BuildStreamError) -> Self {
+        match e {
+            cpal::BuildStreamError::DeviceNotAvailable => Self::NoOutputDevice,
+            e => Self::BuildStream(e),
+        }
+    }
+}
+
+impl From<cpal::PlayStreamError> for MetronomeError {
+    fn from(e: cpal::PlayStreamError) -> Self {
+        match e {
+            cpal::PlayStreamError::DeviceNotAvailable => Self::NoOutputDevice,
+            e @ cpal::PlayStreamError::BackendSpecific { .. } => Self::PlayStream(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for MetronomeError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for MetronomeError {
+    fn from(_: std::sync::PoisonError<T>) -> Self {
+        Self::Poisoned
+    }
+}
+
+/// Checks that a tempo can be played.
+pub(crate) fn validate_bpm(bpm: f64) -> Result<(), MetronomeError> {
+    if bpm.is_finite() && bpm > 0.0 {
+        Ok(())
+    } else {
+        Err(MetronomeError::InvalidConfig(format!(
+            "BPM must be a positive number, got {bpm}"
+        )))
+    }
+}
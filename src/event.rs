@@ -0,0 +1,237 @@
+use std::sync::{
+    Arc, Mutex, PoisonError,
+    atomic::{AtomicU64, Ordering},
+    mpsc,
+};
+use std::task::Waker;
+use std::time::{Duration, Instant};
+
+use crate::accent::AccentLevel;
+use crate::schedule::{ClickKind, ScheduledClick};
+
+/// A click played by a metronome, with its place in the bar and when it sounds.
+///
+/// Events are passed to callbacks registered with [`Metronome::on_beat`](crate::Metronome::on_beat)
+/// and sent on channels opened with [`Metronome::events`](crate::Metronome::events).
+///
+/// Bars and beats are counted from 0 at the start of playback. Without measures,
+/// every beat is a bar of its own.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BeatEvent {
+    /// Bar the click belongs to
+    pub bar: u64,
+    /// Beat within the bar
+    pub beat_in_bar: u32,
+    /// Beat counted from the start of playback
+    pub beat: u64,
+    /// Subdivision within the beat (0 for the main beat)
+    pub subdivision: u32,
+    /// Role of the click within the bar
+    pub kind: ClickKind,
+    /// Accent level of the beat, or `None` for subdivisions
+    pub level: Option<AccentLevel>,
+    /// Frame on the sink's clock at which the click starts
+    pub frame: u64,
+    /// Time at which the click is scheduled to start on the sink's clock
+    pub scheduled_at: Instant,
+    /// Estimated time at which the click becomes audible, after the output's latency
+    pub audible_at: Instant,
+    /// Whether the click belongs to the count-in rather than the main click.
+    ///
+    /// Count-in bars are counted from 0 on their own, and the main click's bars start
+    /// again from 0 after them.
+    pub count_in: bool,
+    /// Whether the click is silent because a [`GapTrainer`](crate::GapTrainer) mutes its bar
+    pub muted: bool,
+}
+
+impl BeatEvent {
+    /// Creates the event for a scheduled click.
+    pub(crate) fn new(
+        click: &ScheduledClick,
+        beats_per_measure: Option<u32>,
+        level: Option<AccentLevel>,
+        scheduled_at: Instant,
+        latency: Duration,
+    ) -> Self {
+        let beats = u64::from(beats_per_measure.unwrap_or(1).max(1));
+        Self {
+            bar: click.beat / beats,
+            #[allow(clippy::cast_possible_truncation)]
+            beat_in_bar: (click.beat % beats) as u32,
+            beat: click.beat,
+            subdivision: click.subdivision,
+            kind: click.kind,
+            level,
+            frame: click.frame,
+            scheduled_at,
+            audible_at: scheduled_at + latency,
+            count_in: false,
+            muted: false,
+        }
+    }
+}
+
+/// Identifies a callback registered with [`Metronome::on_beat`](crate::Metronome::on_beat).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CallbackId(u64);
+
+static CALLBACK_ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A function called for every click.
+type BeatCallback = Arc<dyn Fn(&BeatEvent) + Send + Sync>;
+
+/// The task an async reader of an event channel is waiting in, if any.
+pub(crate) type WakerSlot = Mutex<Option<Waker>>;
+
+/// An open event channel.
+struct EventSender {
+    sender: mpsc::Sender<BeatEvent>,
+    /// Woken whenever the channel gets an event or closes, for channels read by async streams
+    waker: Option<Arc<WakerSlot>>,
+    /// Run of the playback thread the channel listens to; it closes when that run ends
+    run: u64,
+}
+
+/// The open event channels, and the runs that have already ended.
+#[derive(Default)]
+struct EventSenders {
+    open: Vec<EventSender>,
+    /// Latest run that has ended; channels for it or an earlier run close as soon as they open
+    closed_through: u64,
+}
+
+impl EventSenders {
+    /// Adds a channel, or drops it at once if its run has already ended.
+    fn push(&mut self, sender: EventSender) {
+        if sender.run > self.closed_through {
+            self.open.push(sender);
+        }
+    }
+}
+
+/// Wakes the task waiting in a slot, if any.
+fn wake(slot: Option<&WakerSlot>) {
+    if let Some(waker) =
+        slot.and_then(|slot| slot.lock().unwrap_or_else(PoisonError::into_inner).take())
+    {
+        waker.wake();
+    }
+}
+
+/// Beat callbacks and event channels shared between metronome handles and the playback thread.
+#[derive(Clone, Default)]
+pub(crate) struct BeatListeners {
+    callbacks: Arc<Mutex<Vec<(CallbackId, BeatCallback)>>>,
+    senders: Arc<Mutex<EventSenders>>,
+}
+
+impl BeatListeners {
+    pub(crate) fn add(&self, callback: BeatCallback) -> CallbackId {
+        let id = CallbackId(CALLBACK_ID_COUNTER.fetch_add(1, Ordering::Relaxed));
+        self.lock().push((id, callback));
+        id
+    }
+
+    pub(crate) fn remove(&self, id: CallbackId) {
+        self.lock().retain(|(other, _)| *other != id);
+    }
+
+    pub(crate) fn has_callbacks(&self) -> bool {
+        !self.lock().is_empty()
+    }
+
+    pub(crate) fn has_senders(&self, run: u64) -> bool {
+        self.lock_senders()
+            .open
+            .iter()
+            .any(|sender| sender.run == run)
+    }
+
+    /// Opens a channel that receives every event sent from now on, until `run` ends.
+    pub(crate) fn subscribe(&self, run: u64) -> mpsc::Receiver<BeatEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.lock_senders().push(EventSender {
+            sender,
+            waker: None,
+            run,
+        });
+        receiver
+    }
+
+    /// Opens a channel like [`BeatListeners::subscribe`] that also wakes the task in `waker`.
+    #[cfg(feature = "async")]
+    pub(crate) fn subscribe_waking(
+        &self,
+        run: u64,
+        waker: Arc<WakerSlot>,
+    ) -> mpsc::Receiver<BeatEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.lock_senders().push(EventSender {
+            sender,
+            waker: Some(waker),
+            run,
+        });
+        receiver
+    }
+
+    /// Sends an event of `run` to the channels listening to it, forgetting channels whose
+    /// receiver was dropped.
+    pub(crate) fn send(&self, run: u64, event: &BeatEvent) {
+        self.lock_senders().open.retain(|sender| {
+            if sender.run != run {
+                return true;
+            }
+            let open = sender.sender.send(event.clone()).is_ok();
+            wake(sender.waker.as_deref());
+            open
+        });
+    }
+
+    /// Closes the channels listening to `run` or an earlier run, ending their receivers'
+    /// iterators and streams. Channels for later runs stay open.
+    pub(crate) fn close(&self, run: u64) {
+        let senders: Vec<EventSender> = {
+            let mut senders = self.lock_senders();
+            senders.closed_through = senders.closed_through.max(run);
+            let (closed, open) = senders.open.drain(..).partition(|sender| sender.run <= run);
+            senders.open = open;
+            closed
+        };
+        for EventSender { sender, waker, .. } in senders {
+            // Readers must find the channel closed once they are woken
+            drop(sender);
+            wake(waker.as_deref());
+        }
+    }
+
+    /// Calls every callback with the event, without holding the lock so callbacks may
+    /// register or remove callbacks themselves.
+    pub(crate) fn dispatch(&self, event: &BeatEvent) {
+        let callbacks: Vec<BeatCallback> = self
+            .lock()
+            .iter()
+            .map(|(_, callback)| Arc::clone(callback))
+            .collect();
+        for callback in callbacks {
+            callback(event);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<(CallbackId, BeatCallback)>> {
+        self.callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_senders(&self) -> std::sync::MutexGuard<'_, EventSenders> {
+        self.senders.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Estimates the time at which a frame on a sink's clock is played, given the current frame.
+pub(crate) fn frame_instant(frame: u64, now_frame: u64, sample_rate: u32) -> Instant {
+    #[allow(clippy::cast_precision_loss)]
+    let ahead = frame.saturating_sub(now_frame) as f64 / f64::from(sample_rate.max(1));
+    Instant::now() + Duration::from_secs_f64(ahead)
+}
@@ -0,0 +1,126 @@
+use crate::error::MetronomeError;
+use crate::random::unit_random;
+
+/// Mutes bars of a metronome so players can check that they keep time without the click.
+///
+/// Muted bars keep their place in the count: the beat position carries on, and beat
+/// events still fire with [`BeatEvent::muted`](crate::BeatEvent::muted) set, so a UI can
+/// show where the player should be. Bars are counted from the start of the main click,
+/// after any count-in.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, GapTrainer, Metronome};
+///
+/// // Two bars with the click, then two bars without
+/// let gaps = GapTrainer::fixed(2, 2);
+/// let muted: Vec<bool> = (0..6).map(|bar| gaps.is_muted(bar)).collect();
+/// assert_eq!(muted, [false, false, true, true, false, false]);
+///
+/// let mut metronome = Metronome::offline(90.0, Some(4), AccentConfig::default())?;
+/// metronome.set_gap_trainer(Some(gaps));
+/// # Ok::<(), metronome_rs::MetronomeError>(())
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GapTrainer {
+    /// `play` audible bars followed by `mute` silent bars, repeating
+    Fixed {
+        /// Bars played with the click in every cycle
+        play: u32,
+        /// Bars muted in every cycle
+        mute: u32,
+    },
+    /// Every bar after the first is muted with a probability, drawn from a seeded generator
+    Random {
+        /// Chance of a bar being muted, from 0.0 to 1.0
+        probability: f64,
+        /// Seed of the generator; the same seed always mutes the same bars
+        seed: u64,
+    },
+    /// `play` audible bars followed by a gap that grows by one bar every cycle
+    Progressive {
+        /// Bars played with the click in every cycle
+        play: u32,
+        /// Bars muted in the first cycle
+        first_mute: u32,
+        /// Bars muted once the gap has stopped growing
+        max_mute: u32,
+    },
+}
+
+impl GapTrainer {
+    /// Creates a trainer that plays `play` bars and then mutes `mute` bars, repeating.
+    #[must_use]
+    pub const fn fixed(play: u32, mute: u32) -> Self {
+        Self::Fixed { play, mute }
+    }
+
+    /// Creates a trainer that mutes bars at random with the given probability.
+    ///
+    /// The first bar is always played, so the tempo can be heard before the first gap.
+    #[must_use]
+    pub const fn random(probability: f64, seed: u64) -> Self {
+        Self::Random { probability, seed }
+    }
+
+    /// Creates a trainer that plays `play` bars between gaps growing from `first_mute`
+    /// bars by one bar per cycle, up to `max_mute` bars.
+    #[must_use]
+    pub const fn progressive(play: u32, first_mute: u32, max_mute: u32) -> Self {
+        Self::Progressive {
+            play,
+            first_mute,
+            max_mute,
+        }
+    }
+
+    /// Checks whether a bar, counted from 0 at the start of the main click, is muted.
+    #[must_use]
+    pub fn is_muted(&self, bar: u64) -> bool {
+        match *self {
+            Self::Fixed { play, mute } => {
+                bar % (u64::from(play) + u64::from(mute)).max(1) >= u64::from(play)
+            }
+            Self::Random { probability, seed } => bar > 0 && unit_random(seed, bar) < probability,
+            Self::Progressive {
+                play,
+                first_mute,
+                max_mute,
+            } => {
+                let play = u64::from(play);
+                let mut cycle_start = 0;
+                let mut mute = u64::from(first_mute);
+                while mute < u64::from(max_mute) {
+                    if bar < cycle_start + play + mute {
+                        return bar - cycle_start >= play;
+                    }
+                    cycle_start += play + mute;
+                    mute += 1;
+                }
+                (bar - cycle_start) % (play + mute).max(1) >= play
+            }
+        }
+    }
+
+    /// Checks that the trainer can be played.
+    pub(crate) fn validate(&self) -> Result<(), MetronomeError> {
+        let valid = match *self {
+            Self::Fixed { play, .. } => play > 0,
+            Self::Random { probability, .. } => (0.0..=1.0).contains(&probability),
+            Self::Progressive {
+                play,
+                first_mute,
+                max_mute,
+            } => play > 0 && first_mute <= max_mute,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(MetronomeError::InvalidConfig(format!(
+                "gap trainer needs at least one played bar per cycle, a probability from 0 to 1 \
+                 and a first gap no longer than the last, got {self:?}"
+            )))
+        }
+    }
+}
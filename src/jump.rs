@@ -0,0 +1,95 @@
+use crate::error::{MetronomeError, validate_bpm};
+use crate::random::unit_random;
+use crate::voice::Voice;
+
+/// Jumps a metronome to a random tempo every few bars, for practicing locking in quickly.
+///
+/// Every `every_bars` bars the drill picks a whole-number BPM between `min_bpm` and
+/// `max_bpm` from a seeded generator, so the same seed always plays the same tempos.
+/// The jumps land on bar lines of a running metronome without stopping the click, and
+/// a cue voice can replace the downbeat of each new tempo to announce it.
+///
+/// Bars are counted from the start of the main click, after any count-in.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, Metronome, TempoJumpDrill, Voice, WaveType};
+///
+/// // A new tempo from 70 to 130 BPM every 4 bars, announced by a high click
+/// let cue = Voice::new(2000.0, 60, WaveType::Triangle, 0.7);
+/// let drill = TempoJumpDrill::new(70.0, 130.0, 4, 42).with_cue(cue);
+/// assert_eq!(drill.bpm_at_bar(0), drill.bpm_at_bar(3));
+/// assert!((70.0..=130.0).contains(&drill.bpm_at_bar(4)));
+/// assert!(drill.is_jump(4));
+///
+/// let mut metronome = Metronome::offline(100.0, Some(4), AccentConfig::default())?;
+/// metronome.set_jump_drill(Some(drill));
+/// # Ok::<(), metronome_rs::MetronomeError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TempoJumpDrill {
+    /// Lowest tempo picked
+    pub min_bpm: f64,
+    /// Highest tempo picked
+    pub max_bpm: f64,
+    /// Bars played at each tempo (beats when the metronome has no measures)
+    pub every_bars: u32,
+    /// Seed of the generator; the same seed always picks the same tempos
+    pub seed: u64,
+    /// Voice played instead of the downbeat of every new tempo, if any
+    pub cue: Option<Voice>,
+}
+
+impl TempoJumpDrill {
+    /// Creates a drill that picks a tempo from `min_bpm` to `max_bpm` every `every_bars` bars.
+    #[must_use]
+    pub const fn new(min_bpm: f64, max_bpm: f64, every_bars: u32, seed: u64) -> Self {
+        Self {
+            min_bpm,
+            max_bpm,
+            every_bars,
+            seed,
+            cue: None,
+        }
+    }
+
+    /// Returns a copy of this drill that plays `cue` on the first beat of every new tempo.
+    #[must_use]
+    pub const fn with_cue(mut self, cue: Voice) -> Self {
+        self.cue = Some(cue);
+        self
+    }
+
+    /// Gets the tempo of a bar, counted from 0 at the start of the main click.
+    ///
+    /// A drill with reversed or NaN bounds gives a meaningless tempo rather than panicking;
+    /// metronomes refuse to start with such a drill.
+    #[must_use]
+    pub fn bpm_at_bar(&self, bar: u64) -> f64 {
+        let jump = bar / u64::from(self.every_bars.max(1));
+        let bpm = (self.max_bpm - self.min_bpm).mul_add(unit_random(self.seed, jump), self.min_bpm);
+        // Unlike `clamp`, `max` and `min` don't panic on reversed or NaN bounds
+        bpm.round().max(self.min_bpm).min(self.max_bpm)
+    }
+
+    /// Checks whether a bar starts a new tempo. The first bar doesn't count as a jump.
+    #[must_use]
+    pub fn is_jump(&self, bar: u64) -> bool {
+        bar > 0 && bar.is_multiple_of(u64::from(self.every_bars.max(1)))
+    }
+
+    /// Checks that the drill can be played.
+    pub(crate) fn validate(&self) -> Result<(), MetronomeError> {
+        validate_bpm(self.min_bpm)?;
+        validate_bpm(self.max_bpm)?;
+        if self.every_bars > 0 && self.max_bpm >= self.min_bpm {
+            Ok(())
+        } else {
+            Err(MetronomeError::InvalidConfig(format!(
+                "tempo-jump drill needs at least one bar per tempo and a highest tempo no \
+                 lower than the lowest, got {self:?}"
+            )))
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::error::MetronomeError;
+
+/// Steps a metronome through subdivision levels every few bars, such as quarters,
+/// eighths, triplets, sixteenths and quintuplets.
+///
+/// Each level is a number of clicks per beat, played for `every_bars` bars before the
+/// next level takes over on the bar line. The pulse and the bar count carry on through
+/// every change. After the last level the ladder starts over from the first, or climbs
+/// back down through the levels first when it descends.
+///
+/// Bars are counted from the start of the main click, after any count-in.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, Metronome, SubdivisionLadder};
+///
+/// // Quarters, eighths and triplets for a bar each, then back down
+/// let ladder = SubdivisionLadder::new(vec![1, 2, 3], 1).with_descent(true);
+/// let levels: Vec<u32> = (0..6).map(|bar| ladder.subdivisions_at_bar(bar)).collect();
+/// assert_eq!(levels, [1, 2, 3, 2, 1, 2]);
+///
+/// let mut metronome = Metronome::offline(80.0, Some(4), AccentConfig::default())?;
+/// metronome.set_subdivision_ladder(Some(SubdivisionLadder::standard(2)));
+/// # Ok::<(), metronome_rs::MetronomeError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubdivisionLadder {
+    /// Clicks per beat of each level, in order
+    pub levels: Vec<u32>,
+    /// Bars played at each level (beats when the metronome has no measures)
+    pub every_bars: u32,
+    /// Whether to climb back down through the levels before starting over
+    pub descends: bool,
+}
+
+impl SubdivisionLadder {
+    /// Creates a ladder that plays each of `levels` clicks per beat for `every_bars` bars.
+    #[must_use]
+    pub const fn new(levels: Vec<u32>, every_bars: u32) -> Self {
+        Self {
+            levels,
+            every_bars,
+            descends: false,
+        }
+    }
+
+    /// Creates a ladder of quarters, eighths, triplets, sixteenths and quintuplets
+    /// that climbs back down, changing every `every_bars` bars.
+    #[must_use]
+    pub fn standard(every_bars: u32) -> Self {
+        Self::new(vec![1, 2, 3, 4, 5], every_bars).with_descent(true)
+    }
+
+    /// Returns a copy of this ladder that climbs back down through the levels before
+    /// starting over, or jumps straight back to the first level when `descends` is false.
+    #[must_use]
+    pub const fn with_descent(mut self, descends: bool) -> Self {
+        self.descends = descends;
+        self
+    }
+
+    /// Gets the clicks per beat of a bar, counted from 0 at the start of the main click.
+    #[must_use]
+    pub fn subdivisions_at_bar(&self, bar: u64) -> u32 {
+        let len = self.levels.len();
+        // Going down repeats neither the top level nor the first, which starts the next climb
+        let cycle = if self.descends {
+            (2 * len).saturating_sub(2).max(1)
+        } else {
+            len.max(1)
+        };
+        let step = bar / u64::from(self.every_bars.max(1)) % cycle as u64;
+        let step = usize::try_from(step).unwrap_or_default();
+        let index = if step < len { step } else { cycle - step };
+        self.levels.get(index).copied().unwrap_or(1).max(1)
+    }
+
+    /// Checks that the ladder can be played.
+    pub(crate) fn validate(&self) -> Result<(), MetronomeError> {
+        if self.every_bars > 0 && !self.levels.is_empty() && !self.levels.contains(&0) {
+            Ok(())
+        } else {
+            Err(MetronomeError::InvalidConfig(format!(
+                "subdivision ladder needs at least one level of one or more clicks per beat \
+                 and at least one bar per level, got {self:?}"
+            )))
+        }
+    }
+}
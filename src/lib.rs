@@ -16,7 +16,7 @@
 //!
 //! // Stop the metronome
 //! stop_global_metronome();
-//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! # Ok::<(), metronome_rs::MetronomeError>(())
 //! ```
 //!
 //! ## High-Level Helpers
@@ -29,6 +29,7 @@
 //! - `start_practice_metronome(bpm, beats)` - Optimized for practice with subtle accents
 //! - `start_performance_metronome(bpm, beats)` - Optimized for performance with strong accents
 //! - `start_custom_metronome(bpm, beats, config)` - Full customization control
+//! - `start_metronome_with_count_in(bpm, beats, count_in)` - Count-in bars before the main click
 //!
 //! ## Subdivision Support
 //!
@@ -42,8 +43,29 @@
 //! ## Modules
 //!
 //! - `audio` - Audio device and configuration utilities
+//! - `count_in` - Count-in bars played before the main click
+//! - `detect` - Tempo and downbeat detection for recordings
+//! - `error` - The `MetronomeError` type returned by fallible functions
+//! - `event` - Beat events passed to callbacks
+//! - `engine` - Persistent audio engine that mixes voices into one output stream per device
+//! - `voice` - Sounds that can be submitted to the audio engine
+//! - `sink` - Audio sink abstraction with null and capturing sinks for testing
 //! - `tone` - Tone generation and playbook functionality
 //! - `metronome` - Metronome implementation with accent support
+//! - `mixer` - Several metronomes playing at once on the same output
+//! - `gap` - Gap click trainer muting bars while the beat keeps running
+//! - `jump` - Tempo-jump drill picking a random tempo every few bars
+//! - `ladder` - Subdivision ladder changing subdivisions every few bars
+//! - `meter` - Time signatures and beat units
+//! - `polymeter` - Accent cycles of different lengths over the same pulse
+//! - `polyrhythm` - Several pulse streams played across the same span
+//! - `schedule` - Sample-accurate click scheduling
+//! - `stream` - Async streams of beat events (requires the `async` feature)
+//! - `swing` - Swing feels for pairs of subdivisions
+//! - `speed` - Speed trainer raising the tempo every few bars
+//! - `tap` - Tap tempo estimation
+//! - `ramp` - Tempo ramps (accelerando / ritardando)
+//! - `render` - Offline rendering of click tracks to WAV files or sample buffers
 //! - `accent` - Accent configuration for metronomes
 
 // Be a perfectionist, no code is good enough!
@@ -62,24 +84,59 @@
 
 pub mod accent;
 pub mod audio;
+pub mod count_in;
+pub mod detect;
+pub mod engine;
+pub mod error;
+pub mod event;
+pub mod gap;
+pub mod jump;
+pub mod ladder;
+pub mod meter;
 pub mod metronome;
+pub mod mixer;
+pub mod polymeter;
+pub mod polyrhythm;
+pub mod ramp;
+pub mod render;
+pub mod schedule;
+pub mod sink;
+pub mod speed;
+pub mod swing;
+pub mod tap;
 pub mod tone;
+pub mod voice;
+
+mod random;
 
 #[cfg(feature = "python")]
 pub mod python;
 
+#[cfg(feature = "async")]
+pub mod stream;
+
 #[cfg(test)]
 mod tests;
 
 // Re-export commonly used items for convenience
-pub use accent::{AccentConfig, WaveType};
+pub use accent::{AccentConfig, AccentLevel, LevelSounds, WaveType};
 pub use audio::{get_default_host, get_default_output_config, get_default_output_device};
+pub use count_in::{CountIn, CountInSound};
+pub use detect::{TempoEstimate, detect_tempo, detect_tempo_from_wav, detect_tempo_from_wav_file};
+pub use engine::AudioEngine;
+pub use error::MetronomeError;
+pub use event::{BeatEvent, CallbackId};
+pub use gap::GapTrainer;
+pub use jump::TempoJumpDrill;
+pub use ladder::SubdivisionLadder;
+pub use meter::{BeatUnit, Grouping, TimeSignature};
 pub use metronome::{
     Metronome,
     get_global_metronome,
     play_custom_metronome_for_duration,
     play_metronome_for_duration,
     start_custom_metronome,
+    start_metronome_with_count_in,
     // Subdivision helper functions
     start_metronome_with_eighth_notes,
     start_metronome_with_sixteenth_notes,
@@ -92,12 +149,28 @@ pub use metronome::{
     start_simple_metronome,
     stop_global_metronome,
 };
+pub use mixer::{MetronomeMixer, MixerChannel};
+pub use polymeter::{AccentCycle, Polymeter};
+pub use polyrhythm::{Polyrhythm, PulseStream};
+pub use ramp::{RampCurve, RampLength, TempoRamp};
+pub use render::{
+    BitDepth, ClickSamples, RenderLength, render_iter, render_samples, render_to_wav,
+    render_to_wav_file,
+};
+pub use schedule::{ChangeAt, ClickKind, ClickScheduler, ScheduledClick, click_onset_frame};
+pub use sink::{AudioSink, CapturingSink, NullSink};
+pub use speed::SpeedTrainer;
+#[cfg(feature = "async")]
+pub use stream::BeatStream;
+pub use swing::Swing;
+pub use tap::TapTempo;
 pub use tone::{
     beep, beep_frequency, create_sine_wave_generator, play_beep_with_config,
     play_beep_with_config_and_params, play_beep_with_wave_type,
-    play_beep_with_wave_type_and_volume, play_default_beep, play_tone, play_tone_with_wave_type,
-    play_tone_with_wave_type_and_volume,
+    play_beep_with_wave_type_and_volume, play_default_beep, play_tone, play_tone_on_sink,
+    play_tone_with_wave_type, play_tone_with_wave_type_and_volume, wave_value,
 };
+pub use voice::Voice;
 
 // Re-export Python bindings when feature is enabled
 #[cfg(feature = "python")]
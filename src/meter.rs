@@ -0,0 +1,275 @@
+use std::fmt;
+
+use crate::error::MetronomeError;
+
+/// A note value that a tempo is counted in, such as a quarter or a dotted quarter.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::BeatUnit;
+///
+/// // A dotted quarter lasts as long as three eighths
+/// assert!((BeatUnit::DOTTED_QUARTER.length() - 3.0 * BeatUnit::EIGHTH.length()).abs() < 1e-12);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BeatUnit {
+    /// Note value as a fraction of a whole note (1 = whole, 2 = half, 4 = quarter, 8 = eighth, ...)
+    pub note: u32,
+    /// Whether the note is dotted (one and a half times as long)
+    pub dotted: bool,
+}
+
+impl BeatUnit {
+    /// A half note
+    pub const HALF: Self = Self::new(2);
+    /// A quarter note
+    pub const QUARTER: Self = Self::new(4);
+    /// An eighth note
+    pub const EIGHTH: Self = Self::new(8);
+    /// A sixteenth note
+    pub const SIXTEENTH: Self = Self::new(16);
+    /// A dotted half note
+    pub const DOTTED_HALF: Self = Self::dotted(2);
+    /// A dotted quarter note, the pulse of 6/8, 9/8 and 12/8
+    pub const DOTTED_QUARTER: Self = Self::dotted(4);
+    /// A dotted eighth note, the pulse of 6/16 and 12/16
+    pub const DOTTED_EIGHTH: Self = Self::dotted(8);
+
+    /// Creates a plain note value.
+    #[must_use]
+    pub const fn new(note: u32) -> Self {
+        Self {
+            note,
+            dotted: false,
+        }
+    }
+
+    /// Creates a dotted note value.
+    #[must_use]
+    pub const fn dotted(note: u32) -> Self {
+        Self { note, dotted: true }
+    }
+
+    /// Returns the length of the note in whole notes.
+    #[must_use]
+    pub fn length(&self) -> f64 {
+        let length = 1.0 / f64::from(self.note.max(1));
+        if self.dotted { length * 1.5 } else { length }
+    }
+}
+
+impl fmt::Display for BeatUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "1/{}{}", self.note, if self.dotted { "." } else { "" })
+    }
+}
+
+/// Most notes a [`Grouping`] can span
+const MAX_GROUPED_NOTES: u32 = 64;
+
+/// How the notes of an additive meter are grouped, such as 2+2+3 for 7/8.
+///
+/// Groups of zero notes are ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Grouping {
+    /// Bit `n` is set when note `n` starts a group
+    starts: u64,
+    notes: u32,
+}
+
+impl Grouping {
+    /// Creates a grouping from the length of each group in notes.
+    #[must_use]
+    pub fn new(groups: &[u32]) -> Self {
+        let mut starts = 0;
+        let mut notes: u32 = 0;
+        for &group in groups.iter().filter(|&&group| group > 0) {
+            if notes < MAX_GROUPED_NOTES {
+                starts |= 1 << notes;
+            }
+            notes = notes.saturating_add(group);
+        }
+        Self { starts, notes }
+    }
+
+    /// Gets the total number of notes in all groups.
+    #[must_use]
+    pub const fn notes(&self) -> u32 {
+        self.notes
+    }
+
+    /// Gets the length of each group in notes.
+    #[must_use]
+    pub fn groups(&self) -> Vec<u32> {
+        let mut groups = Vec::new();
+        let mut start = 0;
+        for note in 1..=self.notes.min(MAX_GROUPED_NOTES) {
+            if note == self.notes.min(MAX_GROUPED_NOTES) || self.is_group_start(note) {
+                groups.push(note - start);
+                start = note;
+            }
+        }
+        groups
+    }
+
+    /// Checks whether the note at `note` (counted from the start of the measure) starts a group.
+    #[must_use]
+    pub const fn is_group_start(&self, note: u32) -> bool {
+        note < MAX_GROUPED_NOTES && self.starts & (1 << note) != 0
+    }
+}
+
+impl fmt::Display for Grouping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups: Vec<String> = self.groups().iter().map(ToString::to_string).collect();
+        write!(f, "{}", groups.join("+"))
+    }
+}
+
+/// A time signature, such as 4/4, 3/4 or 6/8.
+///
+/// Compound meters (6/8, 9/8, 12/8, 6/16, ...) are counted in dotted pulses: 6/8 has two
+/// beats of a dotted quarter each. By default only those pulses click; use
+/// [`TimeSignature::with_every_note`] to also click the notes in between, grouped in threes.
+///
+/// Additive meters such as 7/8 are given a [`Grouping`] with [`TimeSignature::with_grouping`].
+/// They are counted in notes of the denominator (eighth = 210 for a fast 7/8), click on the
+/// first note of each group, and give those notes a secondary accent.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{BeatUnit, TimeSignature};
+///
+/// let six_eight = TimeSignature::new(6, 8);
+/// assert!(six_eight.is_compound());
+/// assert_eq!(six_eight.beats_per_measure(), 2);
+/// assert_eq!(six_eight.beat_unit(), BeatUnit::DOTTED_QUARTER);
+///
+/// // Click all six eighths instead of only the two pulses
+/// assert_eq!(six_eight.with_every_note().clicks_per_beat(), 3);
+///
+/// // 7/8 grouped 2+2+3, counted in eighths
+/// let seven_eight = TimeSignature::new(7, 8).with_grouping(&[2, 2, 3]);
+/// assert_eq!(seven_eight.beats_per_measure(), 7);
+/// assert_eq!(seven_eight.beat_unit(), BeatUnit::EIGHTH);
+/// assert_eq!(seven_eight.to_string(), "7/8 (2+2+3)");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeSignature {
+    /// Number of notes in a measure
+    pub numerator: u32,
+    /// Note value counted by the numerator (4 = quarter, 8 = eighth, ...)
+    pub denominator: u32,
+    /// In compound and additive meters, click every note rather than only the first of each group
+    pub every_note: bool,
+    /// Grouping of the notes for additive meters, such as 2+2+3
+    pub grouping: Option<Grouping>,
+}
+
+impl TimeSignature {
+    /// Creates a time signature.
+    #[must_use]
+    pub const fn new(numerator: u32, denominator: u32) -> Self {
+        Self {
+            numerator,
+            denominator,
+            every_note: false,
+            grouping: None,
+        }
+    }
+
+    /// Returns a copy of this time signature that clicks every note of a compound or additive meter.
+    ///
+    /// The first note of each group (of three in compound meters) is the beat, and the
+    /// others are played with the subdivision sound. Simple meters are unaffected.
+    #[must_use]
+    pub const fn with_every_note(mut self) -> Self {
+        self.every_note = true;
+        self
+    }
+
+    /// Returns a copy of this time signature with its notes grouped, such as `&[2, 2, 3]` for 7/8.
+    #[must_use]
+    pub fn with_grouping(mut self, groups: &[u32]) -> Self {
+        self.grouping = Some(Grouping::new(groups));
+        self
+    }
+
+    /// Checks whether this is a compound meter, counted in groups of three notes.
+    ///
+    /// A time signature with a custom grouping is additive rather than compound.
+    #[must_use]
+    pub const fn is_compound(&self) -> bool {
+        self.grouping.is_none()
+            && self.denominator >= 8
+            && self.numerator > 3
+            && self.numerator.is_multiple_of(3)
+    }
+
+    /// Gets the number of beats (pulses) in a measure.
+    ///
+    /// Additive meters count every note as a beat, so 7/8 has 7 beats whatever its grouping.
+    #[must_use]
+    pub const fn beats_per_measure(&self) -> u32 {
+        if self.is_compound() {
+            self.numerator / 3
+        } else {
+            self.numerator
+        }
+    }
+
+    /// Gets the note value of one beat, which is dotted in compound meters.
+    #[must_use]
+    pub const fn beat_unit(&self) -> BeatUnit {
+        if self.is_compound() {
+            BeatUnit::dotted(self.denominator / 2)
+        } else {
+            BeatUnit::new(self.denominator)
+        }
+    }
+
+    /// Gets the number of clicks played per beat, before any subdivisions.
+    #[must_use]
+    pub const fn clicks_per_beat(&self) -> u32 {
+        if self.every_note && self.is_compound() {
+            3
+        } else {
+            1
+        }
+    }
+
+    /// Checks that the time signature can be played.
+    pub(crate) fn validate(&self) -> Result<(), MetronomeError> {
+        if self.numerator == 0 || !self.denominator.is_power_of_two() {
+            return Err(MetronomeError::InvalidConfig(format!(
+                "invalid time signature {self}"
+            )));
+        }
+        if let Some(grouping) = self.grouping {
+            if grouping.notes() > MAX_GROUPED_NOTES {
+                return Err(MetronomeError::InvalidConfig(format!(
+                    "groupings can't span more than {MAX_GROUPED_NOTES} notes"
+                )));
+            }
+            if grouping.notes() != self.numerator {
+                return Err(MetronomeError::InvalidConfig(format!(
+                    "grouping {grouping} doesn't add up to the {} notes of {self}",
+                    self.numerator
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)?;
+        if let Some(grouping) = self.grouping {
+            write!(f, " ({grouping})")?;
+        }
+        Ok(())
+    }
+}
@@ -1,31 +1,113 @@
 use cpal::{Device, StreamConfig};
+use std::collections::VecDeque;
 use std::sync::{
-    Arc, Mutex,
-    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex, PoisonError,
+    atomic::{AtomicU64, Ordering},
+    mpsc,
 };
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::accent::AccentConfig;
+use crate::accent::{AccentConfig, AccentLevel};
 use crate::audio::{get_default_host, get_default_output_config, get_default_output_device};
+use crate::count_in::{CountIn, CountInClicks};
+use crate::engine::AudioEngine;
+use crate::error::{MetronomeError, validate_bpm};
+use crate::event::{BeatEvent, BeatListeners, CallbackId, frame_instant};
+use crate::gap::GapTrainer;
+use crate::jump::TempoJumpDrill;
+use crate::ladder::SubdivisionLadder;
+use crate::meter::{BeatUnit, TimeSignature};
+use crate::polymeter::Polymeter;
+use crate::polyrhythm::Polyrhythm;
+use crate::ramp::TempoRamp;
+use crate::schedule::{ChangeAt, ClickKind, ClickScheduler, ScheduledClick};
+use crate::sink::AudioSink;
+use crate::speed::SpeedTrainer;
+use crate::swing::Swing;
+use crate::voice::Voice;
 
 /// Global metronome instance to ensure only one metronome can play at a time
 static GLOBAL_METRONOME: Mutex<Option<Arc<Metronome>>> = Mutex::new(None);
 
 /// A metronome that can play at a specified BPM with optional measure accents.
+///
+/// Clones share their tempo with the original, so any clone can be used as a
+/// handle to change the tempo of a playing metronome.
 #[derive(Clone)]
 pub struct Metronome {
-    bpm: f64,
-    beats_per_measure: Option<u32>,
-    is_playing: Arc<AtomicBool>,
-    device: Arc<Device>,
-    config: StreamConfig,
+    tempo: Arc<Mutex<TempoControl>>,
+    time_signature: Option<TimeSignature>,
+    beat_unit: Option<BeatUnit>,
+    polyrhythm: Option<Polyrhythm>,
+    polymeter: Option<Polymeter>,
+    swing: Swing,
+    count_in: Option<CountIn>,
+    gap_trainer: Option<GapTrainer>,
+    speed_trainer: Option<SpeedTrainer>,
+    jump_drill: Option<TempoJumpDrill>,
+    subdivision_ladder: Option<SubdivisionLadder>,
+    /// Number of the scheduling thread's current run, odd while playing. Every start and
+    /// stop moves it on, so a thread whose run is over knows to exit.
+    run: Arc<AtomicU64>,
+    output: Output,
     accent_config: AccentConfig,
+    pub(crate) listeners: BeatListeners,
     id: Arc<AtomicU64>, // Unique ID for this metronome instance
 }
 
 static METRONOME_ID_COUNTER: AtomicU64 = AtomicU64::new(0);
 
+/// Where a metronome plays its clicks.
+#[derive(Clone)]
+enum Output {
+    /// The default output device, opened when the metronome starts
+    DefaultDevice,
+    /// A specific device and configuration
+    Device(Arc<Device>, StreamConfig),
+    /// Any audio sink
+    Sink(Arc<dyn AudioSink>),
+}
+
+impl Output {
+    /// Opens the sink clicks are submitted to.
+    fn open(&self) -> Result<Arc<dyn AudioSink>, MetronomeError> {
+        // Devices go through their shared engine instead of opening a stream per click
+        let engine = match self {
+            Self::DefaultDevice => AudioEngine::default_output()?,
+            Self::Device(device, config) => AudioEngine::for_device(device, config)?,
+            Self::Sink(sink) => return Ok(Arc::clone(sink)),
+        };
+        Ok(engine)
+    }
+}
+
+/// Tempo shared between metronome handles and the playback thread.
+#[derive(Debug)]
+struct TempoControl {
+    bpm: f64,
+    /// Ramp leading to `bpm`, if the last change was a ramp
+    ramp: Option<TempoRamp>,
+    /// Set when the tempo changed and the playback thread hasn't picked it up yet
+    pending: Option<ChangeAt>,
+}
+
+impl TempoControl {
+    fn shared(bpm: f64) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            bpm,
+            ramp: None,
+            pending: None,
+        }))
+    }
+}
+
+/// How far ahead of the sink's clock clicks are queued, in milliseconds
+pub(crate) const SCHEDULE_AHEAD_MS: u64 = 100;
+
+/// How often the scheduling thread wakes up to queue more clicks, in milliseconds
+const SCHEDULER_INTERVAL_MS: u64 = 10;
+
 impl Metronome {
     /// Creates a new metronome with the specified BPM.
     ///
@@ -40,26 +122,10 @@ impl Metronome {
     ///
     /// # Errors
     ///
-    /// Returns an error if the default audio device or configuration cannot be obtained.
-    pub fn new(
-        bpm: f64,
-        beats_per_measure: Option<u32>,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let host = get_default_host();
-        let device = get_default_output_device(&host)?;
-        let config = get_default_output_config(&device)?;
-
-        Ok(Self {
-            bpm,
-            beats_per_measure,
-            is_playing: Arc::new(AtomicBool::new(false)),
-            device: Arc::new(device),
-            config: config.into(),
-            accent_config: AccentConfig::default(),
-            id: Arc::new(AtomicU64::new(
-                METRONOME_ID_COUNTER.fetch_add(1, Ordering::Relaxed),
-            )),
-        })
+    /// Returns [`MetronomeError::InvalidConfig`] if `bpm` isn't a positive number, or an
+    /// error if the default audio device or configuration cannot be obtained.
+    pub fn new(bpm: f64, beats_per_measure: Option<u32>) -> Result<Self, MetronomeError> {
+        Self::new_with_accent(bpm, beats_per_measure, AccentConfig::default())
     }
 
     /// Creates a new metronome with custom accent configuration.
@@ -72,69 +138,412 @@ impl Metronome {
     ///
     /// # Errors
     ///
-    /// Returns an error if the default audio device or configuration cannot be obtained.
+    /// Returns [`MetronomeError::InvalidConfig`] if `bpm` isn't a positive number, or an
+    /// error if the default audio device or configuration cannot be obtained.
     pub fn new_with_accent(
         bpm: f64,
         beats_per_measure: Option<u32>,
         accent_config: AccentConfig,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
+    ) -> Result<Self, MetronomeError> {
+        // A bad tempo is reported even on machines without an output device
+        validate_bpm(bpm)?;
         let host = get_default_host();
         let device = get_default_output_device(&host)?;
         let config = get_default_output_config(&device)?;
-
-        Ok(Self {
-            bpm,
-            beats_per_measure,
-            is_playing: Arc::new(AtomicBool::new(false)),
-            device: Arc::new(device),
-            config: config.into(),
-            accent_config,
-            id: Arc::new(AtomicU64::new(
-                METRONOME_ID_COUNTER.fetch_add(1, Ordering::Relaxed),
-            )),
-        })
+        let output = Output::Device(Arc::new(device), config.into());
+        Self::build(bpm, beats_per_measure, output, accent_config)
     }
 
     /// Creates a new metronome with custom audio device and configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MetronomeError::InvalidConfig`] if `bpm` isn't a positive number.
     pub fn with_device_config(
         bpm: f64,
         beats_per_measure: Option<u32>,
         device: Device,
         config: StreamConfig,
-    ) -> Self {
-        Self {
+    ) -> Result<Self, MetronomeError> {
+        let output = Output::Device(Arc::new(device), config);
+        Self::build(bpm, beats_per_measure, output, AccentConfig::default())
+    }
+
+    /// Creates a metronome that isn't tied to an audio device.
+    ///
+    /// This is meant for offline rendering (see [`crate::render`]) and works on machines
+    /// without any audio device. If the metronome is started, it plays on the default
+    /// output device.
+    ///
+    /// # Arguments
+    ///
+    /// * `bpm` - Beats per minute (e.g., 120.0)
+    /// * `beats_per_measure` - Optional number of beats per measure for accented first beat
+    /// * `accent_config` - Accent configuration for the click sounds
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MetronomeError::InvalidConfig`] if `bpm` isn't a positive number.
+    pub fn offline(
+        bpm: f64,
+        beats_per_measure: Option<u32>,
+        accent_config: AccentConfig,
+    ) -> Result<Self, MetronomeError> {
+        Self::build(bpm, beats_per_measure, Output::DefaultDevice, accent_config)
+    }
+
+    /// Creates a metronome that plays its clicks on the given audio sink.
+    ///
+    /// Use a [`NullSink`](crate::sink::NullSink) to run a metronome without an audio
+    /// device, or a [`CapturingSink`](crate::sink::CapturingSink) to record the clicks
+    /// it plays.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use metronome_rs::{CapturingSink, Metronome};
+    /// use std::sync::Arc;
+    ///
+    /// let sink = Arc::new(CapturingSink::new(48000, 2));
+    /// let metronome = Metronome::with_sink(120.0, Some(4), sink.clone())?;
+    /// assert!(!metronome.is_playing());
+    /// # Ok::<(), metronome_rs::MetronomeError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MetronomeError::InvalidConfig`] if `bpm` isn't a positive number.
+    pub fn with_sink(
+        bpm: f64,
+        beats_per_measure: Option<u32>,
+        sink: Arc<dyn AudioSink>,
+    ) -> Result<Self, MetronomeError> {
+        Self::build(
             bpm,
             beats_per_measure,
-            is_playing: Arc::new(AtomicBool::new(false)),
-            device: Arc::new(device),
-            config,
-            accent_config: AccentConfig::default(),
+            Output::Sink(sink),
+            AccentConfig::default(),
+        )
+    }
+
+    /// Creates a metronome playing on `output` with the default settings, after checking
+    /// the tempo. Every public constructor goes through here.
+    fn build(
+        bpm: f64,
+        beats_per_measure: Option<u32>,
+        output: Output,
+        accent_config: AccentConfig,
+    ) -> Result<Self, MetronomeError> {
+        validate_bpm(bpm)?;
+        Ok(Self {
+            tempo: TempoControl::shared(bpm),
+            time_signature: beats_per_measure.map(|beats| TimeSignature::new(beats, 4)),
+            beat_unit: None,
+            polyrhythm: None,
+            polymeter: None,
+            swing: Swing::STRAIGHT,
+            count_in: None,
+            gap_trainer: None,
+            speed_trainer: None,
+            jump_drill: None,
+            subdivision_ladder: None,
+            run: Arc::new(AtomicU64::new(0)),
+            output,
+            accent_config,
+            listeners: BeatListeners::default(),
             id: Arc::new(AtomicU64::new(
                 METRONOME_ID_COUNTER.fetch_add(1, Ordering::Relaxed),
             )),
-        }
+        })
     }
 
     /// Gets the current BPM.
+    ///
+    /// While a change made with [`Metronome::set_bpm_at`] is waiting for its beat or bar,
+    /// this already returns the new tempo. During a tempo ramp it returns the ramp's target.
     #[must_use]
-    pub const fn bpm(&self) -> f64 {
-        self.bpm
+    pub fn bpm(&self) -> f64 {
+        self.tempo
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .bpm
+    }
+
+    /// Sets the BPM. If the metronome is playing, the new tempo applies from the next click.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MetronomeError::InvalidConfig`] if `bpm` isn't a positive number; the
+    /// tempo is left unchanged.
+    pub fn set_bpm(&self, bpm: f64) -> Result<(), MetronomeError> {
+        self.set_bpm_at(bpm, ChangeAt::Immediately)
+    }
+
+    /// Sets the BPM, choosing when the change takes effect on a playing metronome.
+    ///
+    /// The beat and bar position are kept, so the metronome carries on counting at the new tempo.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MetronomeError::InvalidConfig`] if `bpm` isn't a positive number; the
+    /// tempo is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use metronome_rs::{ChangeAt, Metronome};
+    /// use std::{thread, time::Duration};
+    ///
+    /// let metronome = Metronome::new(100.0, Some(4))?;
+    /// metronome.start()?;
+    /// thread::sleep(Duration::from_secs(4));
+    ///
+    /// // Speed up at the start of the next bar
+    /// metronome.set_bpm_at(120.0, ChangeAt::NextBar)?;
+    /// thread::sleep(Duration::from_secs(4));
+    ///
+    /// metronome.stop();
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_bpm_at(&self, bpm: f64, at: ChangeAt) -> Result<(), MetronomeError> {
+        validate_bpm(bpm)?;
+        let mut tempo = self.tempo.lock().unwrap_or_else(PoisonError::into_inner);
+        tempo.bpm = bpm;
+        tempo.ramp = None;
+        tempo.pending = Some(at);
+        drop(tempo);
+        Ok(())
     }
 
-    /// Sets the BPM.
-    pub const fn set_bpm(&mut self, bpm: f64) {
-        self.bpm = bpm;
+    /// Ramps the tempo gradually (accelerando or ritardando), starting at the click chosen by `at`.
+    ///
+    /// If the metronome isn't playing yet, the ramp starts with the first click. Once
+    /// the ramp is over the metronome keeps playing at the ramp's target tempo.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MetronomeError::InvalidConfig`] if either end of the ramp isn't a
+    /// positive number; the tempo is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use metronome_rs::{ChangeAt, Metronome, RampCurve, RampLength, TempoRamp};
+    /// use std::{thread, time::Duration};
+    ///
+    /// let metronome = Metronome::new(80.0, Some(4))?;
+    /// metronome.start()?;
+    ///
+    /// // Accelerate to 120 BPM over 8 bars, starting at the next downbeat
+    /// let ramp = TempoRamp::new(80.0, 120.0, RampLength::Bars(8)).with_curve(RampCurve::Exponential);
+    /// metronome.ramp_tempo(ramp, ChangeAt::NextBar)?;
+    /// thread::sleep(Duration::from_secs(30));
+    ///
+    /// metronome.stop();
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn ramp_tempo(&self, ramp: TempoRamp, at: ChangeAt) -> Result<(), MetronomeError> {
+        ramp.validate()?;
+        let mut tempo = self.tempo.lock().unwrap_or_else(PoisonError::into_inner);
+        tempo.bpm = ramp.to_bpm;
+        tempo.ramp = Some(ramp);
+        tempo.pending = Some(at);
+        drop(tempo);
+        Ok(())
     }
 
     /// Gets the beats per measure.
+    ///
+    /// In compound meters this counts the dotted pulses, so 6/8 has 2 beats per measure.
+    /// While a polyrhythm is set, a measure is one cycle of the polyrhythm, and while a
+    /// polymeter is set, a measure is its first accent cycle.
     #[must_use]
-    pub const fn beats_per_measure(&self) -> Option<u32> {
-        self.beats_per_measure
+    pub fn beats_per_measure(&self) -> Option<u32> {
+        if let Some(polyrhythm) = &self.polyrhythm {
+            return Some(polyrhythm.beats_per_cycle());
+        }
+        if let Some(polymeter) = &self.polymeter {
+            return Some(polymeter.beats_per_measure());
+        }
+        self.time_signature
+            .map(|time_signature| time_signature.beats_per_measure())
     }
 
-    /// Sets the beats per measure.
+    /// Sets the beats per measure, using a time signature in quarter notes (e.g. 3 for 3/4).
     pub const fn set_beats_per_measure(&mut self, beats_per_measure: Option<u32>) {
-        self.beats_per_measure = beats_per_measure;
+        self.time_signature = match beats_per_measure {
+            Some(beats) => Some(TimeSignature::new(beats, 4)),
+            None => None,
+        };
+    }
+
+    /// Gets the time signature.
+    #[must_use]
+    pub const fn time_signature(&self) -> Option<TimeSignature> {
+        self.time_signature
+    }
+
+    /// Sets the time signature. `None` plays every beat without accents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use metronome_rs::{AccentConfig, BeatUnit, Metronome, TimeSignature};
+    ///
+    /// // 6/8 clicking all six eighths, at dotted quarter = 60
+    /// let mut metronome = Metronome::offline(60.0, None, AccentConfig::default())?;
+    /// metronome.set_time_signature(Some(TimeSignature::new(6, 8).with_every_note()));
+    /// assert_eq!(metronome.beats_per_measure(), Some(2));
+    /// assert_eq!(metronome.beat_unit(), BeatUnit::DOTTED_QUARTER);
+    /// # Ok::<(), metronome_rs::MetronomeError>(())
+    /// ```
+    pub const fn set_time_signature(&mut self, time_signature: Option<TimeSignature>) {
+        self.time_signature = time_signature;
+    }
+
+    /// Gets the polyrhythm, if one is set.
+    #[must_use]
+    pub const fn polyrhythm(&self) -> Option<&Polyrhythm> {
+        self.polyrhythm.as_ref()
+    }
+
+    /// Sets a polyrhythm to play instead of the regular beat. `None` goes back to the regular beat.
+    ///
+    /// The first stream of the polyrhythm is played at the metronome's BPM. The
+    /// polyrhythm's streams replace the accent configuration's sounds and subdivisions.
+    ///
+    /// Setting a polyrhythm clears any polymeter.
+    pub fn set_polyrhythm(&mut self, polyrhythm: Option<Polyrhythm>) {
+        if polyrhythm.is_some() {
+            self.polymeter = None;
+        }
+        self.polyrhythm = polyrhythm;
+    }
+
+    /// Gets the polymeter, if one is set.
+    #[must_use]
+    pub const fn polymeter(&self) -> Option<&Polymeter> {
+        self.polymeter.as_ref()
+    }
+
+    /// Sets a polymeter to accent the beat with. `None` goes back to the time signature's accents.
+    ///
+    /// The polymeter's accent cycles are counted in beats of the time signature and
+    /// replace its accents and groupings; the regular and subdivision sounds still come
+    /// from the accent configuration. Setting a polymeter clears any polyrhythm.
+    pub fn set_polymeter(&mut self, polymeter: Option<Polymeter>) {
+        if polymeter.is_some() {
+            self.polyrhythm = None;
+        }
+        self.polymeter = polymeter;
+    }
+
+    /// Gets the swing subdivisions are played with.
+    #[must_use]
+    pub const fn swing(&self) -> Swing {
+        self.swing
+    }
+
+    /// Sets the swing subdivisions are played with, such as [`Swing::TRIPLET`].
+    ///
+    /// Swing applies to eighth, sixteenth and other even subdivisions of the beat, and
+    /// is ignored for odd subdivisions and polyrhythms.
+    pub const fn set_swing(&mut self, swing: Swing) {
+        self.swing = swing;
+    }
+
+    /// Gets the count-in played before the main click, if any.
+    #[must_use]
+    pub const fn count_in(&self) -> Option<&CountIn> {
+        self.count_in.as_ref()
+    }
+
+    /// Sets the bars played before the main click each time the metronome starts.
+    ///
+    /// The count-in is also included in rendered click tracks.
+    pub fn set_count_in(&mut self, count_in: Option<CountIn>) {
+        self.count_in = count_in;
+    }
+
+    /// Gets the gap trainer muting bars of the main click, if any.
+    #[must_use]
+    pub const fn gap_trainer(&self) -> Option<&GapTrainer> {
+        self.gap_trainer.as_ref()
+    }
+
+    /// Sets a gap trainer that mutes bars of the main click while beat events keep firing.
+    pub const fn set_gap_trainer(&mut self, gap_trainer: Option<GapTrainer>) {
+        self.gap_trainer = gap_trainer;
+    }
+
+    /// Gets the speed trainer raising the tempo of the main click, if any.
+    #[must_use]
+    pub const fn speed_trainer(&self) -> Option<&SpeedTrainer> {
+        self.speed_trainer.as_ref()
+    }
+
+    /// Sets a speed trainer that picks the tempo of every bar of the main click.
+    ///
+    /// The main click starts at the trainer's starting tempo instead of [`Metronome::bpm`],
+    /// and the trainer's tempo changes land on bar lines without stopping the click.
+    /// A tempo set while it plays lasts until the trainer's next bar.
+    ///
+    /// Setting a speed trainer clears any tempo-jump drill.
+    pub const fn set_speed_trainer(&mut self, speed_trainer: Option<SpeedTrainer>) {
+        if speed_trainer.is_some() {
+            self.jump_drill = None;
+        }
+        self.speed_trainer = speed_trainer;
+    }
+
+    /// Gets the tempo-jump drill picking random tempos for the main click, if any.
+    #[must_use]
+    pub const fn jump_drill(&self) -> Option<&TempoJumpDrill> {
+        self.jump_drill.as_ref()
+    }
+
+    /// Sets a tempo-jump drill that picks the tempo of every bar of the main click.
+    ///
+    /// Like a speed trainer, the drill replaces [`Metronome::bpm`] and changes tempo on
+    /// bar lines without stopping the click. Setting a drill clears any speed trainer.
+    pub const fn set_jump_drill(&mut self, jump_drill: Option<TempoJumpDrill>) {
+        if jump_drill.is_some() {
+            self.speed_trainer = None;
+        }
+        self.jump_drill = jump_drill;
+    }
+
+    /// Gets the subdivision ladder stepping through subdivision levels, if any.
+    #[must_use]
+    pub const fn subdivision_ladder(&self) -> Option<&SubdivisionLadder> {
+        self.subdivision_ladder.as_ref()
+    }
+
+    /// Sets a subdivision ladder that picks the subdivisions of every bar of the main click.
+    ///
+    /// The ladder replaces the accent configuration's `subdivisions`, and its changes
+    /// land on bar lines without breaking the pulse. It is ignored for polyrhythms.
+    pub fn set_subdivision_ladder(&mut self, subdivision_ladder: Option<SubdivisionLadder>) {
+        self.subdivision_ladder = subdivision_ladder;
+    }
+
+    /// Gets the note value the BPM is counted in.
+    ///
+    /// Unless set with [`Metronome::set_beat_unit`], this is the beat of the time
+    /// signature (a dotted quarter in 6/8), or a quarter note without a time signature.
+    #[must_use]
+    pub const fn beat_unit(&self) -> BeatUnit {
+        match self.beat_unit {
+            Some(beat_unit) => beat_unit,
+            None => self.pulse_unit(),
+        }
+    }
+
+    /// Sets the note value the BPM is counted in, such as an eighth note for 6/8 at eighth = 180.
+    ///
+    /// `None` counts the BPM in the beat of the time signature.
+    pub const fn set_beat_unit(&mut self, beat_unit: Option<BeatUnit>) {
+        self.beat_unit = beat_unit;
     }
 
     /// Gets the accent configuration.
@@ -144,29 +553,131 @@ impl Metronome {
     }
 
     /// Sets the accent configuration.
-    pub const fn set_accent_config(&mut self, accent_config: AccentConfig) {
+    pub fn set_accent_config(&mut self, accent_config: AccentConfig) {
         self.accent_config = accent_config;
     }
 
+    /// Registers a callback that is called for every click the metronome plays.
+    ///
+    /// With a polyrhythm, only grid positions where at least one stream plays are clicks.
+    ///
+    /// Callbacks run on the metronome's scheduling thread when their click is due, within
+    /// a few milliseconds of [`BeatEvent::scheduled_at`], so they should return quickly.
+    /// Callbacks are shared by all clones of the metronome and can be registered while
+    /// it plays.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use metronome_rs::{AccentLevel, Metronome, NullSink};
+    /// use std::sync::Arc;
+    ///
+    /// let metronome = Metronome::with_sink(120.0, Some(4), Arc::new(NullSink::default()))?;
+    /// let id = metronome.on_beat(|event| {
+    ///     if event.level == Some(AccentLevel::Strong) {
+    ///         println!("Bar {} starts", event.bar + 1);
+    ///     }
+    /// });
+    /// metronome.remove_callback(id);
+    /// # Ok::<(), metronome_rs::MetronomeError>(())
+    /// ```
+    pub fn on_beat<F>(&self, callback: F) -> CallbackId
+    where
+        F: Fn(&BeatEvent) + Send + Sync + 'static,
+    {
+        self.listeners.add(Arc::new(callback))
+    }
+
+    /// Removes a callback registered with [`Metronome::on_beat`]. Unknown ids are ignored.
+    pub fn remove_callback(&self, id: CallbackId) {
+        self.listeners.remove(id);
+    }
+
+    /// Opens a channel that receives an event for every click the metronome plays.
+    ///
+    /// Events are sent as soon as their click is queued, about 100 ms before
+    /// [`BeatEvent::scheduled_at`], so consumers can line up with the sound using
+    /// [`BeatEvent::audible_at`]. Nothing runs on the scheduling thread on the
+    /// consumer's behalf. The channel closes when the metronome stops, and dropping the
+    /// receiver closes it too. Channels are shared by all clones of the metronome.
+    ///
+    /// A channel opened while the metronome is stopped receives the events of its next
+    /// start.
+    #[must_use]
+    pub fn events(&self) -> mpsc::Receiver<BeatEvent> {
+        self.listeners.subscribe(self.listening_run())
+    }
+
+    /// Starts the metronome and returns a channel receiving an event for every click.
+    ///
+    /// This is the same as calling [`Metronome::events`] and then [`Metronome::start`],
+    /// except that the channel only receives the events of this start, even if the
+    /// metronome was already playing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use metronome_rs::{Metronome, NullSink};
+    /// use std::sync::Arc;
+    ///
+    /// let metronome = Metronome::with_sink(240.0, Some(4), Arc::new(NullSink::default()))?;
+    /// let events = metronome.start_with_events()?;
+    /// let first = events.recv()?;
+    /// assert_eq!((first.bar, first.beat_in_bar), (0, 0));
+    /// metronome.stop();
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Metronome::start`].
+    pub fn start_with_events(&self) -> Result<mpsc::Receiver<BeatEvent>, MetronomeError> {
+        self.start_limited(None, |run| self.listeners.subscribe(run))
+    }
+
     /// Checks if the metronome is currently playing.
     #[must_use]
     pub fn is_playing(&self) -> bool {
-        self.is_playing.load(Ordering::Relaxed)
+        self.run.load(Ordering::Relaxed) % 2 == 1
     }
 
     /// Starts the metronome. This will stop any currently playing metronome globally.
     ///
     /// # Errors
     ///
-    /// Returns an error if there's an issue with thread creation or other system resources.
+    /// Returns [`MetronomeError::InvalidConfig`] if the tempo isn't a positive number or the
+    /// time signature is invalid, or
+    /// [`MetronomeError::Poisoned`] if another thread panicked while starting a metronome.
+    pub fn start(&self) -> Result<(), MetronomeError> {
+        self.start_limited(None, |_| ())
+    }
+
+    /// Starts the metronome like [`Metronome::start`] and stops it once `bars` bars have played.
+    ///
+    /// Without measures, every beat counts as a bar. Event channels close once the last
+    /// bar is over.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// May panic if the global metronome mutex is poisoned due to a previous panic in another thread.
-    pub fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Returns the same errors as [`Metronome::start`].
+    pub fn start_for_bars(&self, bars: u64) -> Result<(), MetronomeError> {
+        self.start_limited(Some(bars), |_| ())
+    }
+
+    /// Starts the metronome globally, stopping it after `bars` bars if given.
+    ///
+    /// `subscribe` is called with the new run before its thread starts, so listeners it
+    /// adds for the run can't miss its first events.
+    pub(crate) fn start_limited<T>(
+        &self,
+        bars: Option<u64>,
+        subscribe: impl FnOnce(u64) -> T,
+    ) -> Result<T, MetronomeError> {
+        self.validate()?;
+
         // Stop any currently playing metronome
         let current_metronome = {
-            let mut global = GLOBAL_METRONOME.lock().unwrap();
+            let mut global = GLOBAL_METRONOME.lock()?;
             let current = global.take(); // Take ownership and clear the global
             *global = Some(Arc::new(self.clone())); // Set new metronome
             current
@@ -174,108 +685,512 @@ impl Metronome {
 
         // Now stop the previous metronome outside the lock
         if let Some(metronome) = current_metronome {
-            metronome.is_playing.store(false, Ordering::Relaxed);
+            metronome.halt();
+        }
+
+        let run = self.begin_run();
+        let subscribed = subscribe(run);
+        self.spawn(run, None, bars);
+        Ok(subscribed)
+    }
+
+    /// Starts the metronome without stopping any other metronome, for metronomes that
+    /// share an output through a [`MetronomeMixer`](crate::mixer::MetronomeMixer).
+    ///
+    /// The first click is placed at `first_frame` on the sink's clock, or a short time
+    /// ahead of playback if `None`. Does nothing if the metronome is already playing.
+    pub(crate) fn start_unmanaged(&self, first_frame: Option<u64>) -> Result<(), MetronomeError> {
+        self.validate()?;
+        let stopped = self
+            .run
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |run| {
+                (run % 2 == 0).then_some(run + 1)
+            });
+        if let Ok(previous) = stopped {
+            self.spawn(previous + 1, first_frame, None);
         }
+        Ok(())
+    }
 
-        self.is_playing.store(true, Ordering::Relaxed);
+    /// Returns a metronome that shares this one's tempo and settings but plays on `sink`,
+    /// starts and stops on its own, and has callbacks and event channels of its own.
+    pub(crate) fn routed_to(&self, sink: Arc<dyn AudioSink>) -> Self {
+        Self {
+            run: Arc::new(AtomicU64::new(0)),
+            output: Output::Sink(sink),
+            listeners: BeatListeners::default(),
+            id: Arc::new(AtomicU64::new(
+                METRONOME_ID_COUNTER.fetch_add(1, Ordering::Relaxed),
+            )),
+            ..self.clone()
+        }
+    }
 
+    /// Runs the metronome on a new thread for `run`, for `bars` bars if given. Once the
+    /// run is over, the thread closes the event channels opened for it.
+    fn spawn(&self, run: u64, first_frame: Option<u64>, bars: Option<u64>) {
         let metronome = self.clone();
         thread::spawn(move || {
-            metronome.run_metronome();
+            metronome.run_metronome(run, first_frame, bars);
+            metronome.stop_run(run);
+            metronome.listeners.close(run);
         });
+    }
 
-        Ok(())
+    /// Marks the metronome as playing and returns the number of the new run. A thread
+    /// still playing an earlier run exits at its next wake-up.
+    fn begin_run(&self) -> u64 {
+        let previous = self
+            .run
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |run| {
+                Some((run + 1) | 1)
+            })
+            .unwrap_or_default();
+        (previous + 1) | 1
+    }
+
+    /// Marks the metronome as stopped, ending the current run if there is one.
+    fn halt(&self) {
+        let _ = self
+            .run
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |run| {
+                (run % 2 == 1).then_some(run + 1)
+            });
+    }
+
+    /// Marks the metronome as stopped if `run` is still the current run, and returns
+    /// whether it was.
+    fn end_run(&self, run: u64) -> bool {
+        self.run
+            .compare_exchange(run, run + 1, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// Stops the metronome if `run` is still playing, leaving any later run alone.
+    pub(crate) fn stop_run(&self, run: u64) {
+        if self.end_run(run) {
+            self.forget_global();
+        }
+    }
+
+    /// Checks whether `run` is still the current run of a playing metronome.
+    fn is_current_run(&self, run: u64) -> bool {
+        self.run.load(Ordering::Relaxed) == run
+    }
+
+    /// Gets the run that event channels opened now listen to: the current run while
+    /// playing, or the next one while stopped.
+    pub(crate) fn listening_run(&self) -> u64 {
+        self.run.load(Ordering::Relaxed) | 1
     }
 
     /// Stops the metronome.
+    ///
+    /// Event channels of the current run close once its thread has exited, within a few
+    /// milliseconds.
     pub fn stop(&self) {
-        self.is_playing.store(false, Ordering::Relaxed);
-
-        // Remove from global if this is the current metronome
-        if let Ok(mut global) = GLOBAL_METRONOME.lock() {
-            if let Some(current) = global.as_ref() {
-                let current_id = current.id.load(Ordering::Relaxed);
-                let self_id = self.id.load(Ordering::Relaxed);
-                if current_id == self_id {
-                    *global = None;
-                }
+        self.halt();
+        self.forget_global();
+    }
+
+    /// Removes the metronome from the global slot if it is the current global metronome.
+    fn forget_global(&self) {
+        if let Ok(mut global) = GLOBAL_METRONOME.lock()
+            && let Some(current) = global.as_ref()
+        {
+            let current_id = current.id.load(Ordering::Relaxed);
+            let self_id = self.id.load(Ordering::Relaxed);
+            if current_id == self_id {
+                *global = None;
             }
         }
     }
 
     /// Internal method that runs the metronome loop.
-    fn run_metronome(&self) {
-        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-        let beat_duration_ms = (60.0 / self.bpm * 1000.0) as u64;
-        let subdivision_duration_ms = beat_duration_ms / u64::from(self.accent_config.subdivisions);
-        let mut beat_count = 0u32;
-        let mut subdivision_count = 0u32;
-
-        while self.is_playing.load(Ordering::Relaxed) {
-            let is_accent = self.beats_per_measure
-                .is_some_and(|beats| beat_count % beats == 0 && subdivision_count == 0);
-
-            let is_main_beat = subdivision_count == 0;
-
-            // Determine what type of sound to play
-            let (frequency, duration, wave_type, volume) = if is_accent {
-                // Accent beat (first beat of measure)
-                (
-                    self.accent_config.accent_frequency,
-                    self.accent_config.accent_duration,
-                    self.accent_config.accent_wave_type.clone(),
-                    1.0, // Full volume for accents
-                )
-            } else if is_main_beat {
-                // Regular beat (non-accent main beats)
-                (
-                    self.accent_config.regular_frequency,
-                    self.accent_config.regular_duration,
-                    self.accent_config.regular_wave_type.clone(),
-                    1.0, // Full volume for main beats
-                )
-            } else if self.accent_config.subdivisions > 1 {
-                // Subdivision click
-                (
-                    self.accent_config.subdivision_frequency,
-                    self.accent_config.subdivision_duration,
-                    self.accent_config.subdivision_wave_type.clone(),
-                    self.accent_config.subdivision_volume,
-                )
-            } else {
-                // Skip if subdivisions = 1 and it's not a main beat
-                subdivision_count = (subdivision_count + 1) % self.accent_config.subdivisions;
-                if subdivision_count == 0 {
-                    beat_count += 1;
+    ///
+    /// Clicks are placed at exact frames on the sink's clock and queued a short
+    /// time ahead of playback, so the thread's wake-up jitter never reaches the audio.
+    fn run_metronome(&self, run: u64, first_frame: Option<u64>, bars: Option<u64>) {
+        let sink = match self.output.open() {
+            Ok(sink) => sink,
+            Err(e) => {
+                eprintln!("Error opening audio output for metronome: {e}");
+                return;
+            }
+        };
+
+        let lookahead_frames = u64::from(sink.sample_rate()) * SCHEDULE_AHEAD_MS / 1000;
+        let first_frame = first_frame.unwrap_or_else(|| sink.frames_played() + lookahead_frames);
+        let (mut count_in, mut scheduler) = self.click_sources(sink.sample_rate(), first_frame);
+
+        // Playback ends on the first beat after the last bar, once that beat's frame is reached
+        let end_beat = bars.map(|bars| {
+            bars.saturating_mul(u64::from(scheduler.beats_per_measure().unwrap_or(1).max(1)))
+        });
+        let mut end_frame = None;
+
+        let mut pending_events = VecDeque::new();
+        while self.is_current_run(run) {
+            self.apply_tempo_change(&mut scheduler);
+
+            let now = sink.frames_played();
+            let horizon = now + lookahead_frames;
+            let beats_per_measure = scheduler.beats_per_measure();
+            if let Some(clicks) = &mut count_in {
+                while let Some(click) = clicks.next_before(horizon) {
+                    let voices = self.count_in_voices(&click, beats_per_measure);
+                    let event = self.queue_click(&*sink, run, &click, voices, now, |at| {
+                        let level = AccentLevel::for_kind(click.kind);
+                        BeatEvent {
+                            count_in: true,
+                            ..BeatEvent::new(
+                                &click,
+                                beats_per_measure,
+                                level,
+                                at,
+                                sink.output_latency(),
+                            )
+                        }
+                    });
+                    pending_events.extend(event);
+                }
+            }
+            while end_frame.is_none() && scheduler.peek_frame() < horizon {
+                let click = scheduler.next_click();
+                if end_beat.is_some_and(|end_beat| click.beat >= end_beat) {
+                    end_frame = Some(click.frame);
+                    break;
+                }
+                self.prepare_next_bar(&mut scheduler, &click);
+                if self.is_empty_grid_tick(&click) {
+                    continue;
                 }
-                continue;
-            };
-
-            // Play the click using the tone module with volume control
-            if let Err(e) = crate::tone::play_beep_with_wave_type_and_volume(
-                self.device.as_ref(),
-                &self.config,
-                frequency,
-                duration,
-                wave_type,
-                volume,
-            ) {
-                eprintln!("Error playing metronome click: {e}");
+                let voices = self.click_voices(&click);
+                let event = self.queue_click(&*sink, run, &click, voices, now, |at| {
+                    self.beat_event(&click, beats_per_measure, at, sink.output_latency())
+                });
+                pending_events.extend(event);
+            }
+
+            // Callbacks fire once their click is due rather than when it is queued
+            let now = sink.frames_played();
+            while let Some(event) = pending_events.pop_front() {
+                if event.frame > now {
+                    pending_events.push_front(event);
+                    break;
+                }
+                self.listeners.dispatch(&event);
+            }
+
+            if end_frame.is_some_and(|end_frame| now >= end_frame) {
                 break;
             }
 
-            // Update counters
-            subdivision_count = (subdivision_count + 1) % self.accent_config.subdivisions;
-            if subdivision_count == 0 {
-                beat_count += 1;
+            thread::sleep(Duration::from_millis(SCHEDULER_INTERVAL_MS));
+        }
+    }
+
+    /// Plays a click's voices on the sink and hands its event to the listeners of `run`.
+    ///
+    /// `event` builds the click's event from the time it is scheduled for, and is only
+    /// called when someone listens. Returns the event if callbacks are waiting for it.
+    fn queue_click(
+        &self,
+        sink: &dyn AudioSink,
+        run: u64,
+        click: &ScheduledClick,
+        voices: Vec<Voice>,
+        now: u64,
+        event: impl FnOnce(Instant) -> BeatEvent,
+    ) -> Option<BeatEvent> {
+        for voice in voices {
+            sink.play(voice.at_frame(click.frame));
+        }
+        let has_callbacks = self.listeners.has_callbacks();
+        if has_callbacks || self.listeners.has_senders(run) {
+            let event = event(frame_instant(click.frame, now, sink.sample_rate()));
+            // Channels get events as soon as they are queued, to line up ahead of the sound
+            self.listeners.send(run, &event);
+            if has_callbacks {
+                return Some(event);
+            }
+        }
+        None
+    }
+
+    /// Creates the count-in clicks, if any, and the main click's scheduler starting
+    /// right after them, with the first click on `first_frame`.
+    pub(crate) fn click_sources(
+        &self,
+        sample_rate: u32,
+        first_frame: u64,
+    ) -> (Option<CountInClicks>, ClickScheduler) {
+        let scheduler = self.click_scheduler(sample_rate);
+        let count_in = self
+            .count_in
+            .as_ref()
+            .map(|count_in| CountInClicks::new(count_in, &scheduler, first_frame));
+        let main_frame = count_in
+            .as_ref()
+            .map_or(first_frame, CountInClicks::end_frame);
+        (count_in, scheduler.starting_at(main_frame))
+    }
+
+    /// Returns the voices played for a count-in click.
+    pub(crate) fn count_in_voices(
+        &self,
+        click: &ScheduledClick,
+        beats_per_measure: Option<u32>,
+    ) -> Vec<Voice> {
+        self.count_in
+            .as_ref()
+            .map(|count_in| count_in.voice_for(click, beats_per_measure, &self.accent_config))
+            .into_iter()
+            .collect()
+    }
+
+    /// Creates a scheduler for this metronome's clicks, including a tempo change
+    /// that is still waiting to be picked up (such as a ramp set before starting).
+    pub(crate) fn click_scheduler(&self, sample_rate: u32) -> ClickScheduler {
+        let clicks_per_beat = self
+            .polyrhythm
+            .as_ref()
+            .map_or_else(|| self.bar_clicks_per_beat(0), Polyrhythm::ticks_per_beat);
+        let bpm = self.bar_bpm(0).unwrap_or_else(|| self.bpm());
+        let mut scheduler = ClickScheduler::new(
+            bpm * self.pulse_ratio(),
+            self.beats_per_measure(),
+            clicks_per_beat,
+            sample_rate,
+        );
+        if self.polyrhythm.is_none() {
+            scheduler = scheduler.with_swing(self.swing);
+        }
+        if self.polyrhythm.is_none()
+            && self.polymeter.is_none()
+            && let Some(time_signature) = self.time_signature
+            && let Some(grouping) = time_signature.grouping
+        {
+            scheduler = scheduler.with_grouping(grouping, !time_signature.every_note);
+        }
+
+        let tempo = self.tempo.lock().unwrap_or_else(PoisonError::into_inner);
+        if let (Some(ramp), Some(at)) = (tempo.ramp, tempo.pending) {
+            scheduler.start_ramp(self.pulse_ramp(ramp), at);
+        }
+        drop(tempo);
+
+        scheduler
+    }
+
+    /// Hands a tempo change made through any handle over to the scheduler.
+    fn apply_tempo_change(&self, scheduler: &mut ClickScheduler) {
+        let mut tempo = self.tempo.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(at) = tempo.pending.take() {
+            match tempo.ramp {
+                Some(ramp) => scheduler.start_ramp(self.pulse_ramp(ramp), at),
+                None => scheduler.change_bpm(tempo.bpm * self.pulse_ratio(), at),
+            }
+        }
+    }
+
+    /// Gets the tempo a speed trainer or tempo-jump drill picks for a bar of the main click.
+    fn bar_bpm(&self, bar: u64) -> Option<f64> {
+        self.speed_trainer.map_or_else(
+            || self.jump_drill.as_ref().map(|drill| drill.bpm_at_bar(bar)),
+            |speed_trainer| Some(speed_trainer.bpm_at_bar(bar)),
+        )
+    }
+
+    /// Gets the clicks per beat of the time signature in a bar of the main click,
+    /// following a subdivision ladder if there is one.
+    fn bar_clicks_per_beat(&self, bar: u64) -> u32 {
+        let clicks_per_note = self
+            .time_signature
+            .map_or(1, |time_signature| time_signature.clicks_per_beat());
+        let subdivisions = self
+            .subdivision_ladder
+            .as_ref()
+            .map_or(self.accent_config.subdivisions, |ladder| {
+                ladder.subdivisions_at_bar(bar)
+            });
+        clicks_per_note * subdivisions.max(1)
+    }
+
+    /// Sets the scheduler to the tempo and subdivisions picked for the bar after
+    /// `click`, where they differ from the current ones.
+    pub(crate) fn prepare_next_bar(&self, scheduler: &mut ClickScheduler, click: &ScheduledClick) {
+        let next_bar = click.beat / self.beats_per_bar() + 1;
+        if let Some(bpm) = self.bar_bpm(next_bar) {
+            let bpm = bpm * self.pulse_ratio();
+            if (bpm - scheduler.bpm()).abs() > f64::EPSILON {
+                scheduler.change_bpm(bpm, ChangeAt::NextBar);
             }
+        }
+        if self.subdivision_ladder.is_some() && self.polyrhythm.is_none() {
+            let clicks_per_beat = self.bar_clicks_per_beat(next_bar);
+            if clicks_per_beat != scheduler.subdivisions() {
+                scheduler.change_subdivisions(clicks_per_beat, ChangeAt::NextBar);
+            }
+        }
+    }
 
-            // Sleep for the remaining time of the subdivision
-            let sleep_duration = subdivision_duration_ms.saturating_sub(duration);
-            if sleep_duration > 0 {
-                thread::sleep(Duration::from_millis(sleep_duration));
+    /// Gets the cue voice announcing a new tempo, if `click` is the downbeat of one.
+    fn jump_cue(&self, click: &ScheduledClick) -> Option<Voice> {
+        let drill = self.jump_drill.as_ref()?;
+        let beats = self.beats_per_bar();
+        let downbeat = click.subdivision == 0 && click.beat.is_multiple_of(beats);
+        if downbeat && drill.is_jump(click.beat / beats) {
+            drill.cue.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the voices played for a scheduled click; muted and silent clicks have none.
+    pub(crate) fn click_voices(&self, click: &ScheduledClick) -> Vec<Voice> {
+        if self.is_muted(click) {
+            return Vec::new();
+        }
+        if let Some(cue) = self.jump_cue(click) {
+            return vec![cue];
+        }
+        if let Some(polymeter) = &self.polymeter
+            && click.subdivision == 0
+        {
+            let accents = polymeter.accents_at(click.beat);
+            if accents.is_empty() {
+                return vec![self.accent_config.voice_for(ClickKind::Regular)];
+            }
+            return accents;
+        }
+        self.polyrhythm.as_ref().map_or_else(
+            || {
+                self.accent_config
+                    .voice_for_click(click, self.beats_per_measure())
+                    .into_iter()
+                    .collect()
+            },
+            |polyrhythm| polyrhythm.voices_for_click(click),
+        )
+    }
+
+    /// Describes a scheduled click for beat callbacks and event streams.
+    pub(crate) fn beat_event(
+        &self,
+        click: &ScheduledClick,
+        beats_per_measure: Option<u32>,
+        scheduled_at: Instant,
+        latency: Duration,
+    ) -> BeatEvent {
+        BeatEvent {
+            muted: self.is_muted(click),
+            ..BeatEvent::new(
+                click,
+                beats_per_measure,
+                self.click_level(click),
+                scheduled_at,
+                latency,
+            )
+        }
+    }
+
+    /// Checks whether a click is a polyrhythm grid position where no stream plays,
+    /// which isn't reported as a beat.
+    fn is_empty_grid_tick(&self, click: &ScheduledClick) -> bool {
+        self.polyrhythm
+            .as_ref()
+            .is_some_and(|polyrhythm| polyrhythm.voices_for_click(click).is_empty())
+    }
+
+    /// Checks whether a click of the main click falls in a bar muted by the gap trainer.
+    fn is_muted(&self, click: &ScheduledClick) -> bool {
+        self.gap_trainer
+            .as_ref()
+            .is_some_and(|gap_trainer| gap_trainer.is_muted(click.beat / self.beats_per_bar()))
+    }
+
+    /// Gets the number of beats in a bar of the main click (1 when there are no measures).
+    pub(crate) fn beats_per_bar(&self) -> u64 {
+        u64::from(self.beats_per_measure().unwrap_or(1).max(1))
+    }
+
+    /// Gets the accent level of a scheduled click, or `None` for subdivisions.
+    fn click_level(&self, click: &ScheduledClick) -> Option<AccentLevel> {
+        if self.polyrhythm.is_some() {
+            return AccentLevel::for_kind(click.kind);
+        }
+        if let Some(polymeter) = &self.polymeter {
+            if click.subdivision != 0 {
+                return None;
             }
+            let starts_cycle = |beats: u32| click.beat.is_multiple_of(u64::from(beats.max(1)));
+            return Some(if starts_cycle(polymeter.beats_per_measure()) {
+                AccentLevel::Strong
+            } else if polymeter
+                .cycles()
+                .iter()
+                .any(|cycle| starts_cycle(cycle.beats))
+            {
+                AccentLevel::Medium
+            } else {
+                AccentLevel::Weak
+            });
         }
+        self.accent_config
+            .level_for_click(click, self.beats_per_measure())
+    }
+
+    /// Checks that the metronome's settings can be played.
+    pub(crate) fn validate(&self) -> Result<(), MetronomeError> {
+        validate_bpm(self.bpm())?;
+        if let Some(time_signature) = self.time_signature {
+            time_signature.validate()?;
+        }
+        if let Some(polyrhythm) = &self.polyrhythm {
+            polyrhythm.validate()?;
+        }
+        if let Some(polymeter) = &self.polymeter {
+            polymeter.validate()?;
+        }
+        self.swing.validate()?;
+        if let Some(count_in) = &self.count_in {
+            count_in.validate()?;
+        }
+        if let Some(gap_trainer) = &self.gap_trainer {
+            gap_trainer.validate()?;
+        }
+        if let Some(speed_trainer) = &self.speed_trainer {
+            speed_trainer.validate()?;
+        }
+        if let Some(subdivision_ladder) = &self.subdivision_ladder {
+            subdivision_ladder.validate()?;
+        }
+        if let Some(jump_drill) = &self.jump_drill {
+            jump_drill.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Note value of one beat of the time signature, which the scheduler counts in.
+    const fn pulse_unit(&self) -> BeatUnit {
+        match self.time_signature {
+            Some(time_signature) => time_signature.beat_unit(),
+            None => BeatUnit::QUARTER,
+        }
+    }
+
+    /// Converts a BPM counted in the beat unit into beats of the time signature.
+    fn pulse_ratio(&self) -> f64 {
+        self.beat_unit().length() / self.pulse_unit().length()
+    }
+
+    /// Converts a ramp counted in the beat unit into beats of the time signature.
+    fn pulse_ramp(&self, mut ramp: TempoRamp) -> TempoRamp {
+        let ratio = self.pulse_ratio();
+        ramp.from_bpm *= ratio;
+        ramp.to_bpm *= ratio;
+        ramp
     }
 }
 
@@ -291,7 +1206,7 @@ pub fn stop_global_metronome() {
     }; // Release lock before calling stop()
 
     if let Some(metronome) = metronome {
-        metronome.is_playing.store(false, Ordering::Relaxed);
+        metronome.halt();
     }
 }
 
@@ -333,7 +1248,7 @@ pub fn get_global_metronome() -> Option<Arc<Metronome>> {
 /// # Errors
 ///
 /// Returns an error if the audio device or configuration cannot be obtained, or if there's an issue starting the metronome.
-pub fn start_simple_metronome(bpm: f64) -> Result<(), Box<dyn std::error::Error>> {
+pub fn start_simple_metronome(bpm: f64) -> Result<(), MetronomeError> {
     let metronome = Metronome::new(bpm, None)?;
     metronome.start()
 }
@@ -375,7 +1290,7 @@ pub fn start_simple_metronome(bpm: f64) -> Result<(), Box<dyn std::error::Error>
 pub fn start_metronome_with_time_signature(
     bpm: f64,
     beats_per_measure: u32,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), MetronomeError> {
     let metronome = Metronome::new(bpm, Some(beats_per_measure))?;
     metronome.start()
 }
@@ -414,7 +1329,7 @@ pub fn play_metronome_for_duration(
     bpm: f64,
     beats_per_measure: Option<u32>,
     duration_ms: u64,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), MetronomeError> {
     let metronome = Metronome::new(bpm, beats_per_measure)?;
     metronome.start()?;
 
@@ -465,11 +1380,50 @@ pub fn start_custom_metronome(
     bpm: f64,
     beats_per_measure: Option<u32>,
     accent_config: AccentConfig,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), MetronomeError> {
     let metronome = Metronome::new_with_accent(bpm, beats_per_measure, accent_config)?;
     metronome.start()
 }
 
+/// Creates and starts a metronome that counts in before the main click and plays indefinitely.
+///
+/// The count-in plays `count_in.bars` bars of `beats_per_measure` beats, then the
+/// main click starts on the next downbeat. The metronome will play until
+/// `stop_global_metronome()` is called.
+///
+/// # Arguments
+///
+/// * `bpm` - Beats per minute (e.g., 120.0)
+/// * `beats_per_measure` - Number of beats per measure (e.g., 4 for 4/4 time)
+/// * `count_in` - Bars and sound of the count-in
+///
+/// # Examples
+///
+/// ```no_run
+/// use metronome_rs::{CountIn, start_metronome_with_count_in, stop_global_metronome};
+/// use std::{thread, time::Duration};
+///
+/// // One bar of count-in, then 4/4 at 100 BPM
+/// start_metronome_with_count_in(100.0, 4, CountIn::bars(1))?;
+///
+/// thread::sleep(Duration::from_secs(10));
+/// stop_global_metronome();
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the audio device or configuration cannot be obtained, or if there's an issue starting the metronome.
+pub fn start_metronome_with_count_in(
+    bpm: f64,
+    beats_per_measure: u32,
+    count_in: CountIn,
+) -> Result<(), MetronomeError> {
+    let mut metronome = Metronome::new(bpm, Some(beats_per_measure))?;
+    metronome.set_count_in(Some(count_in));
+    metronome.start()
+}
+
 /// Creates and starts a custom metronome that plays for a specific duration.
 ///
 /// This combines the flexibility of custom accent configuration with automatic timing control.
@@ -504,7 +1458,7 @@ pub fn play_custom_metronome_for_duration(
     beats_per_measure: Option<u32>,
     accent_config: AccentConfig,
     duration_ms: u64,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), MetronomeError> {
     let metronome = Metronome::new_with_accent(bpm, beats_per_measure, accent_config)?;
     metronome.start()?;
 
@@ -542,10 +1496,7 @@ pub fn play_custom_metronome_for_duration(
 /// # Errors
 ///
 /// Returns an error if the audio device or configuration cannot be obtained, or if there's an issue starting the metronome.
-pub fn start_practice_metronome(
-    bpm: f64,
-    beats_per_measure: u32,
-) -> Result<(), Box<dyn std::error::Error>> {
+pub fn start_practice_metronome(bpm: f64, beats_per_measure: u32) -> Result<(), MetronomeError> {
     let metronome =
         Metronome::new_with_accent(bpm, Some(beats_per_measure), AccentConfig::subtle())?;
     metronome.start()
@@ -579,10 +1530,7 @@ pub fn start_practice_metronome(
 /// # Errors
 ///
 /// Returns an error if the audio device or configuration cannot be obtained, or if there's an issue starting the metronome.
-pub fn start_performance_metronome(
-    bpm: f64,
-    beats_per_measure: u32,
-) -> Result<(), Box<dyn std::error::Error>> {
+pub fn start_performance_metronome(bpm: f64, beats_per_measure: u32) -> Result<(), MetronomeError> {
     let metronome =
         Metronome::new_with_accent(bpm, Some(beats_per_measure), AccentConfig::strong())?;
     metronome.start()
@@ -618,7 +1566,7 @@ pub fn start_performance_metronome(
 pub fn start_metronome_with_eighth_notes(
     bpm: f64,
     beats_per_measure: Option<u32>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), MetronomeError> {
     let metronome =
         Metronome::new_with_accent(bpm, beats_per_measure, AccentConfig::with_eighth_notes())?;
     metronome.start()
@@ -654,7 +1602,7 @@ pub fn start_metronome_with_eighth_notes(
 pub fn start_metronome_with_sixteenth_notes(
     bpm: f64,
     beats_per_measure: Option<u32>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), MetronomeError> {
     let metronome =
         Metronome::new_with_accent(bpm, beats_per_measure, AccentConfig::with_sixteenth_notes())?;
     metronome.start()
@@ -690,7 +1638,7 @@ pub fn start_metronome_with_sixteenth_notes(
 pub fn start_metronome_with_triplets(
     bpm: f64,
     beats_per_measure: Option<u32>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), MetronomeError> {
     let metronome =
         Metronome::new_with_accent(bpm, beats_per_measure, AccentConfig::with_triplets())?;
     metronome.start()
@@ -730,7 +1678,7 @@ pub fn start_metronome_with_subdivisions(
     beats_per_measure: Option<u32>,
     subdivisions: u32,
     subdivision_volume: f32,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), MetronomeError> {
     let config = AccentConfig::with_custom_subdivisions(subdivisions, 330.0, subdivision_volume);
     let metronome = Metronome::new_with_accent(bpm, beats_per_measure, config)?;
     metronome.start()
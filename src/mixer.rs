@@ -0,0 +1,228 @@
+use std::sync::{
+    Arc, Mutex, PoisonError,
+    atomic::{AtomicU32, Ordering},
+};
+use std::time::Duration;
+
+use crate::engine::AudioEngine;
+use crate::error::MetronomeError;
+use crate::metronome::{Metronome, SCHEDULE_AHEAD_MS};
+use crate::sink::AudioSink;
+use crate::voice::Voice;
+
+/// The sink a mixer channel plays on: the mixer's output, with the channel's gain applied.
+struct ChannelSink {
+    output: Arc<dyn AudioSink>,
+    /// Gain as the bits of an `f32`, so it can be changed while the channel plays
+    gain: AtomicU32,
+}
+
+impl ChannelSink {
+    fn gain(&self) -> f32 {
+        f32::from_bits(self.gain.load(Ordering::Relaxed))
+    }
+
+    fn set_gain(&self, gain: f32) {
+        self.gain.store(gain.max(0.0).to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl AudioSink for ChannelSink {
+    fn sample_rate(&self) -> u32 {
+        self.output.sample_rate()
+    }
+
+    fn channels(&self) -> u16 {
+        self.output.channels()
+    }
+
+    fn frames_played(&self) -> u64 {
+        self.output.frames_played()
+    }
+
+    fn play(&self, mut voice: Voice) {
+        voice.volume *= self.gain();
+        self.output.play(voice);
+    }
+
+    fn output_latency(&self) -> Duration {
+        self.output.output_latency()
+    }
+}
+
+/// A metronome playing through a [`MetronomeMixer`], with its own gain.
+///
+/// Clones refer to the same channel.
+#[derive(Clone)]
+pub struct MixerChannel {
+    metronome: Metronome,
+    sink: Arc<ChannelSink>,
+}
+
+impl MixerChannel {
+    /// Gets the channel's metronome.
+    ///
+    /// It shares its tempo with the metronome the channel was added from, so tempo
+    /// changes made through either one apply to the channel. Its callbacks and event
+    /// channels are its own: register them here to follow the channel's clicks.
+    #[must_use]
+    pub const fn metronome(&self) -> &Metronome {
+        &self.metronome
+    }
+
+    /// Gets the gain applied to the channel's clicks.
+    #[must_use]
+    pub fn gain(&self) -> f32 {
+        self.sink.gain()
+    }
+
+    /// Sets the gain applied to the channel's clicks. 1.0 leaves them unchanged, and
+    /// negative values are treated as 0.0. The new gain applies from the next click queued.
+    pub fn set_gain(&self, gain: f32) {
+        self.sink.set_gain(gain);
+    }
+
+    /// Starts the channel without stopping any other metronome. Does nothing if the
+    /// channel is already playing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MetronomeError::InvalidConfig`] if the metronome's settings can't be played.
+    pub fn start(&self) -> Result<(), MetronomeError> {
+        self.metronome.start_unmanaged(None)
+    }
+
+    /// Stops the channel.
+    pub fn stop(&self) {
+        self.metronome.stop();
+    }
+
+    /// Checks if the channel is currently playing.
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        self.metronome.is_playing()
+    }
+}
+
+/// Plays several metronomes at once on the same output.
+///
+/// Unlike [`Metronome::start`], which stops whatever metronome was playing before,
+/// every metronome added to a mixer plays independently with its own gain. The
+/// output's mixer limits the sum, so overlapping clicks get quieter instead of clipping.
+/// Dropping the mixer stops all of its channels.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, CapturingSink, Metronome, MetronomeMixer};
+/// use std::sync::Arc;
+///
+/// let mixer = MetronomeMixer::with_sink(Arc::new(CapturingSink::default()));
+///
+/// // A quiet quarter-note pulse under a loud three-beat accent pattern
+/// let pulse = mixer.add(&Metronome::offline(120.0, None, AccentConfig::default())?, 0.5);
+/// let bars = mixer.add(&Metronome::offline(120.0, Some(3), AccentConfig::default())?, 1.0);
+/// assert_eq!(mixer.channels().len(), 2);
+///
+/// mixer.start_all()?;
+/// assert!(pulse.is_playing() && bars.is_playing());
+/// mixer.stop_all();
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct MetronomeMixer {
+    output: Arc<dyn AudioSink>,
+    channels: Mutex<Vec<MixerChannel>>,
+}
+
+impl MetronomeMixer {
+    /// Creates a mixer playing on the default output device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the default device or configuration cannot be obtained, or if
+    /// its output stream cannot be opened.
+    pub fn new() -> Result<Self, MetronomeError> {
+        Ok(Self::with_sink(AudioEngine::default_output()?))
+    }
+
+    /// Creates a mixer playing on the given audio sink.
+    #[must_use]
+    pub fn with_sink(output: Arc<dyn AudioSink>) -> Self {
+        Self {
+            output,
+            channels: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Adds a metronome to the mixer with the given gain and returns its channel.
+    ///
+    /// The channel plays a copy of `metronome` that keeps sharing its tempo, but is
+    /// started and stopped through the channel or the mixer rather than with
+    /// [`Metronome::start`]. Settings changed on `metronome` afterwards, other than
+    /// the tempo, don't affect the channel, and callbacks and event channels registered
+    /// on `metronome` don't receive the channel's events.
+    pub fn add(&self, metronome: &Metronome, gain: f32) -> MixerChannel {
+        let sink = Arc::new(ChannelSink {
+            output: Arc::clone(&self.output),
+            gain: AtomicU32::new(0),
+        });
+        sink.set_gain(gain);
+
+        let channel = MixerChannel {
+            metronome: metronome.routed_to(Arc::clone(&sink) as Arc<dyn AudioSink>),
+            sink,
+        };
+        self.lock_channels().push(channel.clone());
+        channel
+    }
+
+    /// Stops a channel and removes it from the mixer.
+    pub fn remove(&self, channel: &MixerChannel) {
+        channel.stop();
+        self.lock_channels()
+            .retain(|other| !Arc::ptr_eq(&other.sink, &channel.sink));
+    }
+
+    /// Gets the channels of the mixer, in the order they were added.
+    #[must_use]
+    pub fn channels(&self) -> Vec<MixerChannel> {
+        self.lock_channels().clone()
+    }
+
+    /// Starts every channel that isn't playing yet, with their first clicks on the same frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MetronomeError::InvalidConfig`] if any channel's settings can't be played,
+    /// in which case no channel is started.
+    pub fn start_all(&self) -> Result<(), MetronomeError> {
+        let channels = self.channels();
+        for channel in &channels {
+            channel.metronome.validate()?;
+        }
+
+        let lookahead_frames = u64::from(self.output.sample_rate()) * SCHEDULE_AHEAD_MS / 1000;
+        let first_frame = self.output.frames_played() + lookahead_frames;
+        for channel in channels.iter().filter(|channel| !channel.is_playing()) {
+            channel.metronome.start_unmanaged(Some(first_frame))?;
+        }
+        Ok(())
+    }
+
+    /// Stops every channel.
+    pub fn stop_all(&self) {
+        for channel in self.channels() {
+            channel.stop();
+        }
+    }
+
+    fn lock_channels(&self) -> std::sync::MutexGuard<'_, Vec<MixerChannel>> {
+        self.channels.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for MetronomeMixer {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
@@ -0,0 +1,124 @@
+use crate::accent::WaveType;
+use crate::error::MetronomeError;
+use crate::polyrhythm::lcm;
+use crate::voice::Voice;
+
+/// One accent cycle of a [`Polymeter`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccentCycle {
+    /// Number of beats in one cycle
+    pub beats: u32,
+    /// Sound played on the first beat of every cycle
+    pub accent: Voice,
+}
+
+/// Two or more accent cycles of different lengths over the same pulse, such as 4/4 against 3/4.
+///
+/// Every beat is played at the metronome's tempo. A beat that starts one or more cycles
+/// plays the accent of each of those cycles; any other beat plays the regular sound of
+/// the metronome's accent configuration. The first cycle is the metronome's measure.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, Metronome, Polymeter};
+///
+/// // A 4-beat and a 3-beat accent cycle, realigning every 12 beats
+/// let polymeter = Polymeter::new(&[4, 3]);
+/// assert_eq!(polymeter.beats_to_realign(), 12);
+///
+/// let mut metronome = Metronome::offline(100.0, None, AccentConfig::default())?;
+/// metronome.set_polymeter(Some(polymeter));
+/// assert_eq!(metronome.beats_per_measure(), Some(4));
+/// # Ok::<(), metronome_rs::MetronomeError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polymeter {
+    cycles: Vec<AccentCycle>,
+}
+
+impl Polymeter {
+    /// Creates a polymeter with one accent cycle per entry of `beats`.
+    ///
+    /// The first cycle is accented like the default accent configuration; every further
+    /// cycle is pitched a fifth above the one before and alternates between triangle and
+    /// sine waves, so the accents can be told apart.
+    #[must_use]
+    pub fn new(beats: &[u32]) -> Self {
+        let mut frequency = 880.0;
+        let cycles = beats
+            .iter()
+            .enumerate()
+            .map(|(index, &beats)| {
+                let wave_type = if index % 2 == 1 {
+                    WaveType::Triangle
+                } else {
+                    WaveType::Sine
+                };
+                let accent = Voice::new(frequency, 150, wave_type, 1.0);
+                frequency *= 1.5;
+                AccentCycle { beats, accent }
+            })
+            .collect();
+
+        Self { cycles }
+    }
+
+    /// Returns a copy of this polymeter with the accent of cycle `index` replaced.
+    ///
+    /// Out-of-range indices are ignored.
+    #[must_use]
+    pub fn with_accent(mut self, index: usize, accent: Voice) -> Self {
+        if let Some(cycle) = self.cycles.get_mut(index) {
+            cycle.accent = accent;
+        }
+        self
+    }
+
+    /// Gets the accent cycles.
+    #[must_use]
+    pub fn cycles(&self) -> &[AccentCycle] {
+        &self.cycles
+    }
+
+    /// Gets the number of beats in the first cycle, which the metronome uses as its measure.
+    #[must_use]
+    pub fn beats_per_measure(&self) -> u32 {
+        self.cycles.first().map_or(1, |cycle| cycle.beats.max(1))
+    }
+
+    /// Gets the number of beats after which all cycles start together again.
+    #[must_use]
+    pub fn beats_to_realign(&self) -> u64 {
+        self.cycles
+            .iter()
+            .map(|cycle| u64::from(cycle.beats.max(1)))
+            .fold(1, lcm)
+    }
+
+    /// Returns the accents played on a beat, counted from the start of playback.
+    ///
+    /// The result is empty for beats that don't start any cycle.
+    #[must_use]
+    pub fn accents_at(&self, beat: u64) -> Vec<Voice> {
+        self.cycles
+            .iter()
+            .filter(|cycle| beat.is_multiple_of(u64::from(cycle.beats.max(1))))
+            .map(|cycle| Voice {
+                start_frame: None,
+                ..cycle.accent.clone()
+            })
+            .collect()
+    }
+
+    /// Checks that the polymeter can be played.
+    pub(crate) fn validate(&self) -> Result<(), MetronomeError> {
+        if self.cycles.is_empty() || self.cycles.iter().any(|cycle| cycle.beats == 0) {
+            return Err(MetronomeError::InvalidConfig(
+                "a polymeter needs at least one accent cycle, each with at least one beat"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
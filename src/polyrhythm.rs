@@ -0,0 +1,177 @@
+use crate::accent::{AccentConfig, WaveType};
+use crate::error::MetronomeError;
+use crate::schedule::{ClickKind, ScheduledClick};
+use crate::voice::Voice;
+
+/// Most grid positions a polyrhythm cycle may have
+const MAX_TICKS_PER_CYCLE: u64 = 1 << 16;
+
+/// One pulse stream of a [`Polyrhythm`].
+#[derive(Clone, Debug)]
+pub struct PulseStream {
+    /// Number of evenly spaced pulses in one cycle
+    pub pulses: u32,
+    /// Sounds of the stream: the accent sound plays on the shared downbeat, the regular
+    /// sound on the other pulses, and beat levels apply to the pulses of the cycle
+    pub sound: AccentConfig,
+}
+
+/// Two or more pulse streams played across the same span, such as 3 against 4.
+///
+/// The pulses of the first stream are the metronome's beats, and one cycle of the
+/// polyrhythm lasts as many beats as the first stream has pulses. All streams click
+/// together on the downbeat of every cycle.
+///
+/// Every stream is placed on one shared grid (12 positions per cycle for 3 against 4),
+/// so the streams stay phase-locked through tempo changes and ramps.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, Metronome, Polyrhythm};
+///
+/// // 3 against 4, with the four at 90 BPM
+/// let mut metronome = Metronome::offline(90.0, None, AccentConfig::default())?;
+/// metronome.set_polyrhythm(Some(Polyrhythm::new(&[4, 3])));
+/// assert_eq!(metronome.beats_per_measure(), Some(4));
+/// # Ok::<(), metronome_rs::MetronomeError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Polyrhythm {
+    streams: Vec<PulseStream>,
+}
+
+impl Polyrhythm {
+    /// Creates a polyrhythm with one stream per entry of `pulses`.
+    ///
+    /// The first stream uses the default accent configuration; every further stream is
+    /// pitched a fifth above the one before and alternates between triangle and sine waves,
+    /// so the streams can be told apart.
+    #[must_use]
+    pub fn new(pulses: &[u32]) -> Self {
+        let mut frequency_ratio = 1.0;
+        let streams = pulses
+            .iter()
+            .enumerate()
+            .map(|(index, &pulses)| {
+                let wave_type = if index % 2 == 1 {
+                    WaveType::Triangle
+                } else {
+                    WaveType::Sine
+                };
+                let sound = AccentConfig::new(
+                    880.0 * frequency_ratio,
+                    440.0 * frequency_ratio,
+                    150,
+                    100,
+                    wave_type.clone(),
+                    wave_type,
+                );
+                frequency_ratio *= 1.5;
+                PulseStream { pulses, sound }
+            })
+            .collect();
+
+        Self { streams }
+    }
+
+    /// Returns a copy of this polyrhythm with the sounds of stream `index` replaced.
+    ///
+    /// Out-of-range indices are ignored.
+    #[must_use]
+    pub fn with_sound(mut self, index: usize, sound: AccentConfig) -> Self {
+        if let Some(stream) = self.streams.get_mut(index) {
+            stream.sound = sound;
+        }
+        self
+    }
+
+    /// Gets the pulse streams.
+    #[must_use]
+    pub fn streams(&self) -> &[PulseStream] {
+        &self.streams
+    }
+
+    /// Gets the number of beats in one cycle (the pulses of the first stream).
+    #[must_use]
+    pub fn beats_per_cycle(&self) -> u32 {
+        self.streams
+            .first()
+            .map_or(1, |stream| stream.pulses.max(1))
+    }
+
+    /// Gets the number of grid positions in one cycle, the least common multiple of all pulse counts.
+    #[must_use]
+    pub fn ticks_per_cycle(&self) -> u64 {
+        self.streams
+            .iter()
+            .map(|stream| u64::from(stream.pulses.max(1)))
+            .fold(1, |ticks, pulses| {
+                lcm(ticks, pulses).min(MAX_TICKS_PER_CYCLE + 1)
+            })
+    }
+
+    /// Gets the number of grid positions per beat, which the metronome uses as subdivisions.
+    pub(crate) fn ticks_per_beat(&self) -> u32 {
+        u32::try_from(self.ticks_per_cycle() / u64::from(self.beats_per_cycle())).unwrap_or(1)
+    }
+
+    /// Returns the voices of all streams that click on a grid position of the cycle.
+    #[must_use]
+    pub fn voices_at(&self, tick: u64) -> Vec<Voice> {
+        let ticks = self.ticks_per_cycle();
+        let tick = tick % ticks;
+        self.streams
+            .iter()
+            .filter_map(|stream| {
+                let spacing = ticks / u64::from(stream.pulses.max(1));
+                if !tick.is_multiple_of(spacing) {
+                    return None;
+                }
+                let pulse = tick / spacing;
+                let click = ScheduledClick {
+                    frame: 0,
+                    beat: pulse,
+                    subdivision: 0,
+                    kind: if pulse == 0 {
+                        ClickKind::Accent
+                    } else {
+                        ClickKind::Regular
+                    },
+                };
+                stream.sound.voice_for_click(&click, Some(stream.pulses))
+            })
+            .collect()
+    }
+
+    /// Returns the voices played for a click scheduled on the polyrhythm's grid.
+    pub(crate) fn voices_for_click(&self, click: &ScheduledClick) -> Vec<Voice> {
+        let ticks_per_beat = u64::from(self.ticks_per_beat());
+        let beat_in_cycle = click.beat % u64::from(self.beats_per_cycle());
+        self.voices_at(beat_in_cycle * ticks_per_beat + u64::from(click.subdivision))
+    }
+
+    /// Checks that the polyrhythm can be played.
+    pub(crate) fn validate(&self) -> Result<(), MetronomeError> {
+        if self.streams.is_empty() || self.streams.iter().any(|stream| stream.pulses == 0) {
+            return Err(MetronomeError::InvalidConfig(
+                "a polyrhythm needs at least one stream, each with at least one pulse".to_string(),
+            ));
+        }
+        if self.ticks_per_cycle() > MAX_TICKS_PER_CYCLE {
+            return Err(MetronomeError::InvalidConfig(format!(
+                "polyrhythm cycles can't have more than {MAX_TICKS_PER_CYCLE} grid positions"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Least common multiple of two positive numbers.
+pub(crate) const fn lcm(a: u64, b: u64) -> u64 {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    a / x * b
+}
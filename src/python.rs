@@ -3,6 +3,8 @@
 //! This module provides Python bindings for the metronome library, allowing
 //! Python code to use the metronome functionality.
 
+#[cfg(feature = "python")]
+use pyo3::create_exception;
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
 #[cfg(feature = "python")]
@@ -10,17 +12,111 @@ use pyo3::types::PyModule;
 
 #[cfg(feature = "python")]
 use crate::{
-    accent::{AccentConfig, WaveType},
+    accent::{AccentConfig, AccentLevel, LevelSounds, WaveType},
+    error::MetronomeError,
+    event::BeatEvent,
     metronome::{
-        play_custom_metronome_for_duration, play_metronome_for_duration, start_custom_metronome,
-        start_metronome_with_eighth_notes, start_metronome_with_sixteenth_notes,
-        start_metronome_with_subdivisions, start_metronome_with_time_signature,
-        start_metronome_with_triplets, start_performance_metronome, start_practice_metronome,
-        start_simple_metronome, stop_global_metronome,
+        Metronome, play_custom_metronome_for_duration, play_metronome_for_duration,
+        start_custom_metronome, start_metronome_with_eighth_notes,
+        start_metronome_with_sixteenth_notes, start_metronome_with_subdivisions,
+        start_metronome_with_time_signature, start_metronome_with_triplets,
+        start_performance_metronome, start_practice_metronome, start_simple_metronome,
+        stop_global_metronome,
     },
+    schedule::ClickKind,
     tone::{beep, beep_frequency},
 };
 
+#[cfg(feature = "python")]
+create_exception!(
+    metronome_rs,
+    PyMetronomeError,
+    pyo3::exceptions::PyRuntimeError,
+    "Base class for all metronome errors"
+);
+#[cfg(feature = "python")]
+create_exception!(
+    metronome_rs,
+    NoOutputDeviceError,
+    PyMetronomeError,
+    "No audio output device is available"
+);
+#[cfg(feature = "python")]
+create_exception!(
+    metronome_rs,
+    UnsupportedSampleFormatError,
+    PyMetronomeError,
+    "The output device uses an unsupported sample format"
+);
+#[cfg(feature = "python")]
+create_exception!(
+    metronome_rs,
+    StreamConfigError,
+    PyMetronomeError,
+    "The output configuration of the device couldn't be retrieved"
+);
+#[cfg(feature = "python")]
+create_exception!(
+    metronome_rs,
+    StreamBuildError,
+    PyMetronomeError,
+    "The output stream couldn't be built"
+);
+#[cfg(feature = "python")]
+create_exception!(
+    metronome_rs,
+    StreamPlayError,
+    PyMetronomeError,
+    "The output stream couldn't be started"
+);
+#[cfg(feature = "python")]
+create_exception!(
+    metronome_rs,
+    InvalidConfigError,
+    PyMetronomeError,
+    "A setting is out of range"
+);
+#[cfg(feature = "python")]
+create_exception!(
+    metronome_rs,
+    PoisonedStateError,
+    PyMetronomeError,
+    "Shared state was poisoned by a panic in another thread"
+);
+#[cfg(feature = "python")]
+create_exception!(
+    metronome_rs,
+    MetronomeIOError,
+    PyMetronomeError,
+    "Reading or writing a file or stream failed"
+);
+#[cfg(feature = "python")]
+create_exception!(
+    metronome_rs,
+    StoppedError,
+    PyMetronomeError,
+    "The metronome was stopped before it finished playing"
+);
+
+#[cfg(feature = "python")]
+/// Converts a metronome error into the matching Python exception
+fn to_py_err(context: &str, error: &MetronomeError) -> PyErr {
+    let message = format!("{context}: {error}");
+    match error {
+        MetronomeError::NoOutputDevice => NoOutputDeviceError::new_err(message),
+        MetronomeError::UnsupportedSampleFormat(_) => {
+            UnsupportedSampleFormatError::new_err(message)
+        }
+        MetronomeError::StreamConfig(_) => StreamConfigError::new_err(message),
+        MetronomeError::BuildStream(_) => StreamBuildError::new_err(message),
+        MetronomeError::PlayStream(_) => StreamPlayError::new_err(message),
+        MetronomeError::InvalidConfig(_) => InvalidConfigError::new_err(message),
+        MetronomeError::Poisoned => PoisonedStateError::new_err(message),
+        MetronomeError::Io(_) => MetronomeIOError::new_err(message),
+        MetronomeError::Stopped => StoppedError::new_err(message),
+    }
+}
+
 #[cfg(feature = "python")]
 #[pyclass]
 #[derive(Clone)]
@@ -122,6 +218,8 @@ impl PyAccentConfig {
                 subdivision_duration: 80,
                 subdivision_wave_type: WaveType::Sine,
                 subdivision_volume: 0.7,
+                beat_levels: Vec::new(),
+                level_sounds: LevelSounds::new(),
             },
         }
     }
@@ -144,8 +242,7 @@ impl PyAccentConfig {
     ) -> Self {
         let accent_wave = accent_wave_type.map_or(WaveType::Sine, |w| w.inner);
         let regular_wave = regular_wave_type.map_or(WaveType::Sine, |w| w.inner);
-        let subdivision_wave = subdivision_wave_type
-            .map_or(WaveType::Sine, |w| w.inner);
+        let subdivision_wave = subdivision_wave_type.map_or(WaveType::Sine, |w| w.inner);
 
         Self {
             inner: AccentConfig {
@@ -160,6 +257,8 @@ impl PyAccentConfig {
                 subdivision_duration,
                 subdivision_wave_type: subdivision_wave,
                 subdivision_volume,
+                beat_levels: Vec::new(),
+                level_sounds: LevelSounds::new(),
             },
         }
     }
@@ -310,58 +409,155 @@ impl PyAccentConfig {
     }
 }
 
+#[cfg(feature = "python")]
+#[pyclass(frozen)]
+#[derive(Clone)]
+/// Python wrapper for `BeatEvent`, passed to beat callbacks
+pub struct PyBeatEvent {
+    inner: BeatEvent,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl PyBeatEvent {
+    /// Bar the click belongs to, counted from 0
+    #[getter]
+    const fn bar(&self) -> u64 {
+        self.inner.bar
+    }
+
+    /// Beat within the bar, counted from 0
+    #[getter]
+    const fn beat_in_bar(&self) -> u32 {
+        self.inner.beat_in_bar
+    }
+
+    /// Beat counted from the start of playback
+    #[getter]
+    const fn beat(&self) -> u64 {
+        self.inner.beat
+    }
+
+    /// Subdivision within the beat (0 for the main beat)
+    #[getter]
+    const fn subdivision(&self) -> u32 {
+        self.inner.subdivision
+    }
+
+    /// Role of the click: `accent`, `secondary_accent`, `regular` or `subdivision`
+    #[getter]
+    const fn kind(&self) -> &'static str {
+        match self.inner.kind {
+            ClickKind::Accent => "accent",
+            ClickKind::SecondaryAccent => "secondary_accent",
+            ClickKind::Regular => "regular",
+            ClickKind::Subdivision => "subdivision",
+        }
+    }
+
+    /// Accent level: `strong`, `medium`, `weak`, `ghost`, `mute`, or `None` for subdivisions
+    #[getter]
+    const fn level(&self) -> Option<&'static str> {
+        match self.inner.level {
+            Some(AccentLevel::Strong) => Some("strong"),
+            Some(AccentLevel::Medium) => Some("medium"),
+            Some(AccentLevel::Weak) => Some("weak"),
+            Some(AccentLevel::Ghost) => Some("ghost"),
+            Some(AccentLevel::Mute) => Some("mute"),
+            None => None,
+        }
+    }
+
+    /// Whether the click belongs to the count-in rather than the main click
+    #[getter]
+    const fn count_in(&self) -> bool {
+        self.inner.count_in
+    }
+
+    /// Whether the click is silent because its bar is muted by a gap trainer
+    #[getter]
+    const fn muted(&self) -> bool {
+        self.inner.muted
+    }
+
+    /// Frame on the audio clock at which the click starts
+    #[getter]
+    const fn frame(&self) -> u64 {
+        self.inner.frame
+    }
+
+    /// Seconds until the click is scheduled to start (0.0 once it has started)
+    #[getter]
+    fn seconds_until_scheduled(&self) -> f64 {
+        self.inner
+            .scheduled_at
+            .saturating_duration_since(std::time::Instant::now())
+            .as_secs_f64()
+    }
+
+    /// Seconds until the click is estimated to become audible (0.0 once it has)
+    #[getter]
+    fn seconds_until_audible(&self) -> f64 {
+        self.inner
+            .audible_at
+            .saturating_duration_since(std::time::Instant::now())
+            .as_secs_f64()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PyBeatEvent(bar={}, beat_in_bar={}, subdivision={}, kind='{}')",
+            self.inner.bar,
+            self.inner.beat_in_bar,
+            self.inner.subdivision,
+            self.kind()
+        )
+    }
+}
+
 #[cfg(feature = "python")]
 /// Play a simple beep sound
 #[pyfunction]
 fn py_beep() -> PyResult<()> {
-    beep().map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to play beep: {e}"))
-    })
+    beep().map_err(|e| to_py_err("Failed to play beep", &e))
 }
 
 #[cfg(feature = "python")]
 /// Play a beep at a specific frequency
 #[pyfunction]
 fn py_beep_frequency(frequency: f32) -> PyResult<()> {
-    beep_frequency(frequency).map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to play beep: {e}"))
-    })
+    beep_frequency(frequency).map_err(|e| to_py_err("Failed to play beep", &e))
 }
 
 #[cfg(feature = "python")]
 /// Start a simple metronome without accents
 #[pyfunction]
 fn py_start_simple_metronome(bpm: f64) -> PyResult<()> {
-    start_simple_metronome(bpm).map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to start metronome: {e}"))
-    })
+    start_simple_metronome(bpm).map_err(|e| to_py_err("Failed to start metronome", &e))
 }
 
 #[cfg(feature = "python")]
 /// Start a metronome with time signature accents
 #[pyfunction]
 fn py_start_metronome_with_time_signature(bpm: f64, beats_per_measure: u32) -> PyResult<()> {
-    start_metronome_with_time_signature(bpm, beats_per_measure).map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to start metronome: {e}"))
-    })
+    start_metronome_with_time_signature(bpm, beats_per_measure)
+        .map_err(|e| to_py_err("Failed to start metronome", &e))
 }
 
 #[cfg(feature = "python")]
 /// Start a practice metronome with subtle accents
 #[pyfunction]
 fn py_start_practice_metronome(bpm: f64, beats_per_measure: u32) -> PyResult<()> {
-    start_practice_metronome(bpm, beats_per_measure).map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to start metronome: {e}"))
-    })
+    start_practice_metronome(bpm, beats_per_measure)
+        .map_err(|e| to_py_err("Failed to start metronome", &e))
 }
 
 #[cfg(feature = "python")]
 /// Start a performance metronome with strong accents
 #[pyfunction]
 fn py_start_performance_metronome(bpm: f64, beats_per_measure: u32) -> PyResult<()> {
-    start_performance_metronome(bpm, beats_per_measure).map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to start metronome: {e}"))
-    })
+    start_performance_metronome(bpm, beats_per_measure)
+        .map_err(|e| to_py_err("Failed to start metronome", &e))
 }
 
 #[cfg(feature = "python")]
@@ -372,36 +568,68 @@ fn py_start_custom_metronome(
     beats_per_measure: Option<u32>,
     accent_config: PyAccentConfig,
 ) -> PyResult<()> {
-    start_custom_metronome(bpm, beats_per_measure, accent_config.inner).map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to start metronome: {e}"))
-    })
+    start_custom_metronome(bpm, beats_per_measure, accent_config.inner)
+        .map_err(|e| to_py_err("Failed to start metronome", &e))
+}
+
+#[cfg(feature = "python")]
+/// Start a metronome that calls `callback` with a `PyBeatEvent` for every click
+///
+/// The callback runs on the metronome's thread, so GUI code should hand the event
+/// over to its own thread (for example with tkinter's `after`). Exceptions raised by
+/// the callback are reported through `sys.unraisablehook` and don't stop the metronome.
+///
+/// This function doesn't return a handle: the metronome replaces the global one, and
+/// `py_stop_global_metronome()` (or starting another metronome) is the only way to stop
+/// it and release the callback.
+#[pyfunction]
+#[pyo3(signature = (bpm, beats_per_measure, callback, accent_config=None))]
+fn py_start_metronome_with_callback(
+    bpm: f64,
+    beats_per_measure: Option<u32>,
+    callback: PyObject,
+    accent_config: Option<PyAccentConfig>,
+) -> PyResult<()> {
+    let accent_config = accent_config.map_or_else(AccentConfig::default, |config| config.inner);
+    let metronome = Metronome::new_with_accent(bpm, beats_per_measure, accent_config)
+        .map_err(|e| to_py_err("Failed to start metronome", &e))?;
+    metronome.on_beat(move |event| {
+        Python::with_gil(|py| {
+            let event = PyBeatEvent {
+                inner: event.clone(),
+            };
+            if let Err(e) = callback.call1(py, (event,)) {
+                e.write_unraisable(py, Some(callback.bind(py)));
+            }
+        });
+    });
+    metronome
+        .start()
+        .map_err(|e| to_py_err("Failed to start metronome", &e))
 }
 
 #[cfg(feature = "python")]
 /// Start a metronome with eighth note subdivisions
 #[pyfunction]
 fn py_start_metronome_with_eighth_notes(bpm: f64, beats_per_measure: u32) -> PyResult<()> {
-    start_metronome_with_eighth_notes(bpm, Some(beats_per_measure)).map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to start metronome: {e}"))
-    })
+    start_metronome_with_eighth_notes(bpm, Some(beats_per_measure))
+        .map_err(|e| to_py_err("Failed to start metronome", &e))
 }
 
 #[cfg(feature = "python")]
 /// Start a metronome with sixteenth note subdivisions
 #[pyfunction]
 fn py_start_metronome_with_sixteenth_notes(bpm: f64, beats_per_measure: u32) -> PyResult<()> {
-    start_metronome_with_sixteenth_notes(bpm, Some(beats_per_measure)).map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to start metronome: {e}"))
-    })
+    start_metronome_with_sixteenth_notes(bpm, Some(beats_per_measure))
+        .map_err(|e| to_py_err("Failed to start metronome", &e))
 }
 
 #[cfg(feature = "python")]
 /// Start a metronome with triplet subdivisions
 #[pyfunction]
 fn py_start_metronome_with_triplets(bpm: f64, beats_per_measure: u32) -> PyResult<()> {
-    start_metronome_with_triplets(bpm, Some(beats_per_measure)).map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to start metronome: {e}"))
-    })
+    start_metronome_with_triplets(bpm, Some(beats_per_measure))
+        .map_err(|e| to_py_err("Failed to start metronome", &e))
 }
 
 #[cfg(feature = "python")]
@@ -419,9 +647,7 @@ fn py_start_metronome_with_subdivisions(
         subdivisions,
         subdivision_volume,
     )
-    .map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to start metronome: {e}"))
-    })
+    .map_err(|e| to_py_err("Failed to start metronome", &e))
 }
 
 #[cfg(feature = "python")]
@@ -432,9 +658,8 @@ fn py_play_metronome_for_duration(
     beats_per_measure: Option<u32>,
     duration_ms: u64,
 ) -> PyResult<()> {
-    play_metronome_for_duration(bpm, beats_per_measure, duration_ms).map_err(|e| {
-        pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to play metronome: {e}"))
-    })
+    play_metronome_for_duration(bpm, beats_per_measure, duration_ms)
+        .map_err(|e| to_py_err("Failed to play metronome", &e))
 }
 
 #[cfg(feature = "python")]
@@ -447,9 +672,7 @@ fn py_play_custom_metronome_for_duration(
     duration_ms: u64,
 ) -> PyResult<()> {
     play_custom_metronome_for_duration(bpm, beats_per_measure, accent_config.inner, duration_ms)
-        .map_err(|e| {
-            pyo3::exceptions::PyRuntimeError::new_err(format!("Failed to play metronome: {e}"))
-        })
+        .map_err(|e| to_py_err("Failed to play metronome", &e))
 }
 
 #[cfg(feature = "python")]
@@ -465,6 +688,23 @@ fn py_stop_global_metronome() {
 fn metronome_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyWaveType>()?;
     m.add_class::<PyAccentConfig>()?;
+    m.add_class::<PyBeatEvent>()?;
+
+    // Exceptions
+    let py = m.py();
+    m.add("MetronomeError", py.get_type::<PyMetronomeError>())?;
+    m.add("NoOutputDeviceError", py.get_type::<NoOutputDeviceError>())?;
+    m.add(
+        "UnsupportedSampleFormatError",
+        py.get_type::<UnsupportedSampleFormatError>(),
+    )?;
+    m.add("StreamConfigError", py.get_type::<StreamConfigError>())?;
+    m.add("StreamBuildError", py.get_type::<StreamBuildError>())?;
+    m.add("StreamPlayError", py.get_type::<StreamPlayError>())?;
+    m.add("InvalidConfigError", py.get_type::<InvalidConfigError>())?;
+    m.add("PoisonedStateError", py.get_type::<PoisonedStateError>())?;
+    m.add("MetronomeIOError", py.get_type::<MetronomeIOError>())?;
+    m.add("StoppedError", py.get_type::<StoppedError>())?;
 
     // Basic functions
     m.add_function(wrap_pyfunction!(py_beep, m)?)?;
@@ -476,6 +716,7 @@ fn metronome_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(py_start_practice_metronome, m)?)?;
     m.add_function(wrap_pyfunction!(py_start_performance_metronome, m)?)?;
     m.add_function(wrap_pyfunction!(py_start_custom_metronome, m)?)?;
+    m.add_function(wrap_pyfunction!(py_start_metronome_with_callback, m)?)?;
 
     // Subdivision functions
     m.add_function(wrap_pyfunction!(py_start_metronome_with_eighth_notes, m)?)?;
@@ -0,0 +1,82 @@
+use crate::error::{MetronomeError, validate_bpm};
+
+/// Shape of a tempo ramp between its start and target tempo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RampCurve {
+    /// BPM changes by the same amount at every step
+    #[default]
+    Linear,
+    /// BPM changes by the same ratio at every step, which sounds even across wide ranges
+    Exponential,
+}
+
+/// How long a tempo ramp lasts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RampLength {
+    /// Number of beats
+    Beats(u32),
+    /// Number of measures (beats when the metronome has no measures)
+    Bars(u32),
+    /// Number of seconds
+    Seconds(f64),
+}
+
+/// A gradual tempo change (accelerando or ritardando).
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{RampCurve, RampLength, TempoRamp};
+///
+/// // Accelerate from 90 to 120 BPM over 8 bars
+/// let ramp = TempoRamp::new(90.0, 120.0, RampLength::Bars(8)).with_curve(RampCurve::Exponential);
+/// assert!((ramp.bpm_at(0.0) - 90.0).abs() < 1e-9);
+/// assert!((ramp.bpm_at(1.0) - 120.0).abs() < 1e-9);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TempoRamp {
+    /// Tempo at the start of the ramp
+    pub from_bpm: f64,
+    /// Tempo at the end of the ramp, kept once the ramp is over
+    pub to_bpm: f64,
+    /// How long the ramp lasts
+    pub length: RampLength,
+    /// Shape of the ramp
+    pub curve: RampCurve,
+}
+
+impl TempoRamp {
+    /// Creates a linear ramp from one tempo to another.
+    #[must_use]
+    pub const fn new(from_bpm: f64, to_bpm: f64, length: RampLength) -> Self {
+        Self {
+            from_bpm,
+            to_bpm,
+            length,
+            curve: RampCurve::Linear,
+        }
+    }
+
+    /// Returns a copy of this ramp with the specified curve.
+    #[must_use]
+    pub const fn with_curve(mut self, curve: RampCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Returns the tempo at `progress` through the ramp, where 0.0 is the start and 1.0 the end.
+    #[must_use]
+    pub fn bpm_at(&self, progress: f64) -> f64 {
+        let progress = progress.clamp(0.0, 1.0);
+        match self.curve {
+            RampCurve::Linear => (self.to_bpm - self.from_bpm).mul_add(progress, self.from_bpm),
+            RampCurve::Exponential => self.from_bpm * (self.to_bpm / self.from_bpm).powf(progress),
+        }
+    }
+
+    /// Checks that both ends of the ramp are playable tempos.
+    pub(crate) fn validate(&self) -> Result<(), MetronomeError> {
+        validate_bpm(self.from_bpm)?;
+        validate_bpm(self.to_bpm)
+    }
+}
@@ -0,0 +1,14 @@
+/// Returns a number in `[0, 1)` that depends only on the seed and a counter such as the
+/// bar (`SplitMix64`).
+pub fn unit_random(seed: u64, bar: u64) -> f64 {
+    let mix = |value: u64| {
+        let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+    // The top 53 bits fill the mantissa of an f64 exactly, and are divided by 2^53
+    #[allow(clippy::cast_precision_loss)]
+    let value = (mix(seed ^ mix(bar)) >> 11) as f64;
+    value / 9_007_199_254_740_992.0
+}
@@ -0,0 +1,494 @@
+//! Offline rendering of metronome click tracks, to WAV files or in-memory samples.
+//!
+//! Rendering uses the same scheduler and voice mixer as live playback, so a
+//! rendered click track sounds exactly like the metronome played through a
+//! device. No audio device is needed.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::count_in::CountInClicks;
+use crate::error::MetronomeError;
+use crate::metronome::Metronome;
+use crate::schedule::ClickScheduler;
+use crate::voice::VoiceMixer;
+
+/// How much of a metronome to render.
+///
+/// A metronome's count-in is rendered before the main click and isn't counted in `Bars`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderLength {
+    /// Number of measures (beats when the metronome has no measures)
+    Bars(u32),
+    /// Length in milliseconds
+    Milliseconds(u64),
+}
+
+/// Sample format of a rendered WAV file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BitDepth {
+    /// 16-bit integer PCM
+    #[default]
+    Int16,
+    /// 24-bit integer PCM
+    Int24,
+    /// 32-bit floating point
+    Float32,
+}
+
+impl BitDepth {
+    /// Gets the number of bits per sample.
+    #[must_use]
+    pub const fn bits(self) -> u16 {
+        match self {
+            Self::Int16 => 16,
+            Self::Int24 => 24,
+            Self::Float32 => 32,
+        }
+    }
+}
+
+/// Renders a metronome's clicks block by block, adding voices as their frames come up.
+pub(crate) struct ClickRenderer {
+    count_in: Option<CountInClicks>,
+    scheduler: ClickScheduler,
+    metronome: Metronome,
+    mixer: VoiceMixer,
+    frame: u64,
+    end_frame: Option<u64>,
+}
+
+impl ClickRenderer {
+    /// Creates a renderer that keeps producing clicks indefinitely.
+    pub(crate) fn new(metronome: &Metronome, sample_rate: u32) -> Self {
+        let (count_in, scheduler) = metronome.click_sources(sample_rate, 0);
+        Self {
+            count_in,
+            scheduler,
+            metronome: metronome.clone(),
+            mixer: VoiceMixer::new(sample_rate),
+            frame: 0,
+            end_frame: None,
+        }
+    }
+
+    /// Creates a renderer that stops after the given length.
+    pub(crate) fn with_length(
+        metronome: &Metronome,
+        length: RenderLength,
+        sample_rate: u32,
+    ) -> Self {
+        let mut renderer = Self::new(metronome, sample_rate);
+        let end_frame = match length {
+            RenderLength::Bars(bars) => {
+                let beats_per_bar = u64::from(metronome.beats_per_measure().unwrap_or(1).max(1));
+                let last_beat = u64::from(bars) * beats_per_bar;
+                // The track ends exactly where the first beat after the last bar would fall
+                let mut scheduler = renderer.scheduler.clone();
+                loop {
+                    let click = scheduler.next_click();
+                    if click.beat >= last_beat {
+                        break click.frame;
+                    }
+                    metronome.prepare_next_bar(&mut scheduler, &click);
+                }
+            }
+            RenderLength::Milliseconds(ms) => ms * u64::from(sample_rate) / 1000,
+        };
+        renderer.end_frame = Some(end_frame);
+        renderer
+    }
+
+    /// Gets the total number of frames this renderer produces, or `None` if it never stops.
+    pub(crate) const fn total_frames(&self) -> Option<u64> {
+        self.end_frame
+    }
+
+    /// Gets the number of frames left to render, or `None` if the renderer never stops.
+    pub(crate) fn remaining_frames(&self) -> Option<u64> {
+        self.end_frame.map(|end| end - self.frame)
+    }
+
+    /// Gets how many frames the next block may hold, at most `max` (0 once rendering is done).
+    pub(crate) fn next_block_len(&self, max: usize) -> usize {
+        self.remaining_frames().map_or(max, |remaining| {
+            usize::try_from(remaining).map_or(max, |remaining| remaining.min(max))
+        })
+    }
+
+    /// Renders the next mono frames into `output`, which must not be longer than [`Self::next_block_len`].
+    pub(crate) fn render_block(&mut self, output: &mut [f32]) {
+        let block_end = self.frame + output.len() as u64;
+        let limit = self.end_frame.map_or(block_end, |end| block_end.min(end));
+        if let Some(count_in) = &mut self.count_in {
+            let beats_per_measure = self.scheduler.beats_per_measure();
+            while let Some(click) = count_in.next_before(limit) {
+                for voice in self.metronome.count_in_voices(&click, beats_per_measure) {
+                    self.mixer.add(voice.at_frame(click.frame), self.frame);
+                }
+            }
+        }
+        while self.scheduler.peek_frame() < limit {
+            let click = self.scheduler.next_click();
+            self.metronome.prepare_next_bar(&mut self.scheduler, &click);
+            for voice in self.metronome.click_voices(&click) {
+                self.mixer.add(voice.at_frame(click.frame), self.frame);
+            }
+        }
+
+        self.mixer.render(output, self.frame);
+        self.frame = block_end;
+    }
+}
+
+/// A lazy iterator over a metronome's audio as interleaved `f32` samples.
+///
+/// Every channel of a frame carries the same value. Created by [`render_iter`]
+/// (bounded) or [`ClickSamples::new`] (endless); samples are rendered in small
+/// blocks as the iterator is advanced.
+pub struct ClickSamples {
+    renderer: ClickRenderer,
+    channels: usize,
+    block: Vec<f32>,
+    frame_in_block: usize,
+    channel: usize,
+}
+
+impl ClickSamples {
+    /// Creates an endless sample iterator for a metronome.
+    ///
+    /// # Arguments
+    ///
+    /// * `metronome` - The metronome to render, including its accent configuration
+    /// * `sample_rate` - Sample rate in Hz (e.g., 48000)
+    /// * `channels` - Number of interleaved channels (e.g., 2 for stereo)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metronome's settings are invalid.
+    pub fn new(
+        metronome: &Metronome,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Result<Self, MetronomeError> {
+        metronome.validate()?;
+        Ok(Self::from_renderer(
+            ClickRenderer::new(metronome, sample_rate),
+            channels,
+        ))
+    }
+
+    fn from_renderer(renderer: ClickRenderer, channels: u16) -> Self {
+        Self {
+            renderer,
+            channels: usize::from(channels.max(1)),
+            block: Vec::new(),
+            frame_in_block: 0,
+            channel: 0,
+        }
+    }
+
+    /// Gets the number of interleaved channels.
+    #[must_use]
+    pub const fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Fills `output` with the next interleaved samples and returns how many were written.
+    ///
+    /// This renders straight into the buffer, which is faster than pulling samples one
+    /// by one. Fewer samples than the buffer holds are only written at the end of a
+    /// bounded render.
+    pub fn fill(&mut self, output: &mut [f32]) -> usize {
+        let mut written = 0;
+        // Finish a partially consumed frame or block first
+        while written < output.len() && self.frame_in_block < self.block.len() {
+            if let Some(sample) = self.next() {
+                output[written] = sample;
+                written += 1;
+            }
+        }
+
+        while written < output.len() {
+            let frames = self
+                .renderer
+                .next_block_len((output.len() - written) / self.channels);
+            if frames == 0 {
+                // Less than a frame of room left; continue sample by sample
+                match self.next() {
+                    Some(sample) => {
+                        output[written] = sample;
+                        written += 1;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+
+            self.block.resize(frames, 0.0);
+            self.renderer.render_block(&mut self.block);
+            for &value in &self.block {
+                output[written..written + self.channels].fill(value);
+                written += self.channels;
+            }
+            self.frame_in_block = self.block.len();
+        }
+
+        written
+    }
+}
+
+impl Iterator for ClickSamples {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.frame_in_block >= self.block.len() {
+            let frames = self.renderer.next_block_len(RENDER_BLOCK_FRAMES);
+            if frames == 0 {
+                return None;
+            }
+            self.block.resize(frames, 0.0);
+            self.renderer.render_block(&mut self.block);
+            self.frame_in_block = 0;
+        }
+
+        let value = self.block[self.frame_in_block];
+        self.channel += 1;
+        if self.channel == self.channels {
+            self.channel = 0;
+            self.frame_in_block += 1;
+        }
+
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let Some(remaining) = self.renderer.remaining_frames() else {
+            return (usize::MAX, None);
+        };
+
+        let buffered = (self.block.len() - self.frame_in_block) * self.channels - self.channel;
+        usize::try_from(remaining)
+            .ok()
+            .and_then(|frames| frames.checked_mul(self.channels))
+            .and_then(|samples| samples.checked_add(buffered))
+            .map_or((usize::MAX, None), |samples| (samples, Some(samples)))
+    }
+}
+
+/// Number of frames rendered at a time when samples are pulled one by one
+const RENDER_BLOCK_FRAMES: usize = 1024;
+
+/// Returns a lazy iterator over a metronome's interleaved samples for the given length.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, Metronome, RenderLength, render_iter};
+///
+/// let metronome = Metronome::offline(120.0, Some(4), AccentConfig::default())?;
+/// let samples = render_iter(&metronome, RenderLength::Bars(1), 48000, 2)?;
+///
+/// // One bar of 4/4 at 120 BPM is 2 seconds of stereo audio
+/// assert_eq!(samples.count(), 2 * 48000 * 2);
+/// # Ok::<(), metronome_rs::MetronomeError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the metronome's settings are invalid.
+pub fn render_iter(
+    metronome: &Metronome,
+    length: RenderLength,
+    sample_rate: u32,
+    channels: u16,
+) -> Result<ClickSamples, MetronomeError> {
+    metronome.validate()?;
+    Ok(ClickSamples::from_renderer(
+        ClickRenderer::with_length(metronome, length, sample_rate),
+        channels,
+    ))
+}
+
+/// Renders a metronome into a buffer of interleaved `f32` samples.
+///
+/// # Arguments
+///
+/// * `metronome` - The metronome to render, including its accent configuration
+/// * `length` - How many bars or milliseconds to render
+/// * `sample_rate` - Sample rate in Hz (e.g., 48000)
+/// * `channels` - Number of interleaved channels (e.g., 2 for stereo)
+///
+/// # Errors
+///
+/// Returns an error if the metronome's settings are invalid.
+pub fn render_samples(
+    metronome: &Metronome,
+    length: RenderLength,
+    sample_rate: u32,
+    channels: u16,
+) -> Result<Vec<f32>, MetronomeError> {
+    let mut samples = render_iter(metronome, length, sample_rate, channels)?;
+    let mut buffer = Vec::new();
+    // Reserve the whole track when it fits in memory, otherwise grow as samples arrive
+    let _ = buffer.try_reserve_exact(samples.size_hint().0);
+
+    let mut block = vec![0.0; RENDER_BLOCK_FRAMES * samples.channels()];
+    loop {
+        let written = samples.fill(&mut block);
+        buffer.extend_from_slice(&block[..written]);
+        if written < block.len() {
+            return Ok(buffer);
+        }
+    }
+}
+
+/// Renders a metronome to a mono WAV file.
+///
+/// # Arguments
+///
+/// * `metronome` - The metronome to render, including its accent configuration
+/// * `length` - How many bars or milliseconds to render
+/// * `sample_rate` - Sample rate of the file in Hz (e.g., 48000)
+/// * `bit_depth` - Sample format of the file
+/// * `path` - Where to write the file
+///
+/// # Examples
+///
+/// ```no_run
+/// use metronome_rs::{AccentConfig, BitDepth, Metronome, RenderLength, render_to_wav_file};
+///
+/// // 16 bars of 4/4 at 96 BPM for a DAW session, no audio device needed
+/// let metronome = Metronome::offline(96.0, Some(4), AccentConfig::strong())?;
+/// render_to_wav_file(&metronome, RenderLength::Bars(16), 48000, BitDepth::Int24, "click.wav")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the metronome's settings are invalid or the file cannot be created or written.
+pub fn render_to_wav_file(
+    metronome: &Metronome,
+    length: RenderLength,
+    sample_rate: u32,
+    bit_depth: BitDepth,
+    path: impl AsRef<Path>,
+) -> Result<(), MetronomeError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    render_to_wav(metronome, length, sample_rate, bit_depth, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Renders a metronome as a mono WAV stream into any writer.
+///
+/// # Errors
+///
+/// Returns an error if the metronome's settings are invalid, writing fails, or the rendered
+/// audio or the sample rate is too large for a WAV file.
+pub fn render_to_wav<W: Write>(
+    metronome: &Metronome,
+    length: RenderLength,
+    sample_rate: u32,
+    bit_depth: BitDepth,
+    writer: &mut W,
+) -> Result<(), MetronomeError> {
+    metronome.validate()?;
+
+    let mut renderer = ClickRenderer::with_length(metronome, length, sample_rate);
+    let frames = renderer.total_frames().unwrap_or_default();
+    let data_len = write_wav_header(writer, frames, 1, sample_rate, bit_depth)?;
+
+    let mut block = vec![0.0; RENDER_BLOCK_FRAMES];
+    loop {
+        let frames = renderer.next_block_len(block.len());
+        if frames == 0 {
+            break;
+        }
+        renderer.render_block(&mut block[..frames]);
+        for &sample in &block[..frames] {
+            write_sample(writer, sample, bit_depth)?;
+        }
+    }
+
+    // RIFF chunks are padded to an even length
+    if data_len % 2 == 1 {
+        writer.write_all(&[0])?;
+    }
+
+    Ok(())
+}
+
+/// Writes the RIFF header of a WAV file holding `frames` frames, and returns the length of
+/// its data chunk in bytes.
+fn write_wav_header<W: Write>(
+    writer: &mut W,
+    frames: u64,
+    channels: u16,
+    sample_rate: u32,
+    bit_depth: BitDepth,
+) -> Result<u32, MetronomeError> {
+    let too_large =
+        |what: &str| MetronomeError::InvalidConfig(format!("{what} is too large for a WAV file"));
+    let bytes_per_sample = bit_depth.bits() / 8;
+    let block_align = channels * bytes_per_sample;
+    let data_len = frames
+        .checked_mul(u64::from(block_align))
+        .ok_or_else(|| too_large("rendered audio"))?;
+    let byte_rate = u32::try_from(u64::from(sample_rate) * u64::from(block_align))
+        .map_err(|_| too_large("sample rate"))?;
+
+    // Float data needs the extended format chunk and a fact chunk
+    let (format_tag, fmt_len, fact_len): (u16, u32, u32) = match bit_depth {
+        BitDepth::Int16 | BitDepth::Int24 => (1, 16, 0),
+        BitDepth::Float32 => (3, 18, 12),
+    };
+    let riff_len = 4 + (8 + u64::from(fmt_len)) + u64::from(fact_len) + 8 + data_len + data_len % 2;
+    let riff_len = u32::try_from(riff_len).map_err(|_| too_large("rendered audio"))?;
+    let data_len = u32::try_from(data_len).map_err(|_| too_large("rendered audio"))?;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&riff_len.to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&fmt_len.to_le_bytes())?;
+    writer.write_all(&format_tag.to_le_bytes())?;
+    writer.write_all(&channels.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&byte_rate.to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&bit_depth.bits().to_le_bytes())?;
+    if fmt_len == 18 {
+        writer.write_all(&0u16.to_le_bytes())?;
+    }
+
+    if fact_len > 0 {
+        writer.write_all(b"fact")?;
+        writer.write_all(&4u32.to_le_bytes())?;
+        writer.write_all(&(data_len / u32::from(block_align)).to_le_bytes())?;
+    }
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())?;
+    Ok(data_len)
+}
+
+/// Writes a single sample in the given format.
+fn write_sample<W: Write>(writer: &mut W, sample: f32, bit_depth: BitDepth) -> std::io::Result<()> {
+    let sample = sample.clamp(-1.0, 1.0);
+    match bit_depth {
+        BitDepth::Int16 => {
+            #[allow(clippy::cast_possible_truncation)]
+            let value = (sample * f32::from(i16::MAX)).round() as i16;
+            writer.write_all(&value.to_le_bytes())
+        }
+        BitDepth::Int24 => {
+            #[allow(clippy::cast_possible_truncation)]
+            let value = (f64::from(sample) * 8_388_607.0).round() as i32;
+            writer.write_all(&value.to_le_bytes()[..3])
+        }
+        BitDepth::Float32 => writer.write_all(&sample.to_le_bytes()),
+    }
+}
@@ -0,0 +1,415 @@
+use crate::meter::Grouping;
+use crate::ramp::{RampLength, TempoRamp};
+use crate::swing::Swing;
+
+/// The role a click plays within the bar, which decides the sound it is played with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClickKind {
+    /// First beat of a measure
+    Accent,
+    /// First beat of a group within the measure, in additive meters such as 2+2+3
+    SecondaryAccent,
+    /// Any other main beat
+    Regular,
+    /// A click between main beats
+    Subdivision,
+}
+
+/// When a change to a running metronome takes effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ChangeAt {
+    /// From the next click that hasn't been scheduled yet
+    #[default]
+    Immediately,
+    /// From the next main beat
+    NextBeat,
+    /// From the first beat of the next measure (the next beat if there are no measures)
+    NextBar,
+}
+
+/// A tempo change waiting for the click it applies at.
+#[derive(Clone, Copy, Debug)]
+enum PendingTempo {
+    Bpm(f64),
+    Ramp(TempoRamp),
+}
+
+/// A tempo ramp that is currently being played.
+#[derive(Clone, Copy, Debug)]
+struct ActiveRamp {
+    ramp: TempoRamp,
+    start_beat: f64,
+    start_position: f64,
+}
+
+/// A metronome click placed at an exact frame on the audio clock.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScheduledClick {
+    /// Frame at which the click starts
+    pub frame: u64,
+    /// Index of the beat this click belongs to, counted from the start of playback
+    pub beat: u64,
+    /// Subdivision within the beat (0 for the main beat)
+    pub subdivision: u32,
+    /// Role of the click within the bar
+    pub kind: ClickKind,
+}
+
+/// Computes the frame at which click number `index` starts, relative to the first click.
+///
+/// The onset is derived from the absolute click position rather than from accumulated
+/// intervals, so fractional tempos and odd subdivisions never drift away from the grid.
+///
+/// # Arguments
+///
+/// * `index` - Number of the click, counting every subdivision
+/// * `bpm` - Beats per minute
+/// * `subdivisions` - Number of clicks per beat
+/// * `sample_rate` - Sample rate of the audio clock in Hz
+#[must_use]
+pub fn click_onset_frame(index: u64, bpm: f64, subdivisions: u32, sample_rate: u32) -> u64 {
+    to_frame(click_offset(index, bpm, subdivisions, sample_rate))
+}
+
+/// Exact (fractional) frame offset of click number `index` at a constant tempo.
+fn click_offset(index: u64, bpm: f64, subdivisions: u32, sample_rate: u32) -> f64 {
+    #[allow(clippy::cast_precision_loss)]
+    let position = index as f64 * 60.0 * f64::from(sample_rate);
+    position / (bpm * f64::from(subdivisions.max(1)))
+}
+
+/// Rounds an exact frame position to the nearest frame.
+const fn to_frame(position: f64) -> u64 {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let frame = position.round() as u64;
+    frame
+}
+
+/// Produces the clicks of a metronome in order, each placed at an exact frame.
+///
+/// The scheduler is independent of any audio device; the metronome uses it to
+/// queue clicks ahead of the audio engine's clock.
+///
+/// Tempo changes start a new segment at the click where they take effect. Within a
+/// segment every onset is computed from the segment's origin, so changing tempo
+/// never disturbs the beat count or accumulates rounding error. While a tempo ramp
+/// is running, the tempo is re-evaluated and a new segment started at every click.
+///
+/// Tempos must be positive; the scheduler doesn't check them, as [`Metronome`](crate::Metronome)
+/// validates every tempo before passing it on.
+#[derive(Clone, Debug)]
+pub struct ClickScheduler {
+    bpm: f64,
+    beats_per_measure: Option<u32>,
+    subdivisions: u32,
+    sample_rate: u32,
+    segment_origin: f64,
+    segment_start: u64,
+    next_index: u64,
+    pending_tempo: Option<(PendingTempo, ChangeAt)>,
+    pending_subdivisions: Option<(u32, ChangeAt)>,
+    ramp: Option<ActiveRamp>,
+    grouping: Option<Grouping>,
+    group_starts_only: bool,
+    swing: Swing,
+}
+
+impl ClickScheduler {
+    /// Creates a scheduler whose first click falls on frame 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `bpm` - Beats per minute (e.g., 120.0)
+    /// * `beats_per_measure` - Optional number of beats per measure for accented first beat
+    /// * `subdivisions` - Number of clicks per beat (1 for no subdivisions)
+    /// * `sample_rate` - Sample rate of the audio clock in Hz
+    #[must_use]
+    pub const fn new(
+        bpm: f64,
+        beats_per_measure: Option<u32>,
+        subdivisions: u32,
+        sample_rate: u32,
+    ) -> Self {
+        Self {
+            bpm,
+            beats_per_measure,
+            subdivisions: if subdivisions == 0 { 1 } else { subdivisions },
+            sample_rate,
+            segment_origin: 0.0,
+            segment_start: 0,
+            next_index: 0,
+            pending_tempo: None,
+            pending_subdivisions: None,
+            ramp: None,
+            grouping: None,
+            group_starts_only: false,
+            swing: Swing::STRAIGHT,
+        }
+    }
+
+    /// Returns a copy of this scheduler whose first click falls on the given frame.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub const fn starting_at(mut self, frame: u64) -> Self {
+        self.segment_origin = frame as f64;
+        self
+    }
+
+    /// Returns a copy of this scheduler whose measures are grouped for an additive meter.
+    ///
+    /// Each beat is one note of the grouping, and a measure lasts as many beats as the
+    /// grouping has notes. The first beat of every later group is a
+    /// [`ClickKind::SecondaryAccent`]. With `group_starts_only`, only the first beat of
+    /// each group clicks, giving an uneven pulse; otherwise the other beats (and any
+    /// subdivisions) are played as [`ClickKind::Subdivision`]s.
+    #[must_use]
+    pub const fn with_grouping(mut self, grouping: Grouping, group_starts_only: bool) -> Self {
+        self.beats_per_measure = Some(grouping.notes());
+        self.grouping = Some(grouping);
+        self.group_starts_only = group_starts_only;
+        self
+    }
+
+    /// Returns a copy of this scheduler that swings pairs of subdivisions.
+    ///
+    /// The second click of every pair is pushed back; beats split into an odd number
+    /// of clicks are played straight.
+    #[must_use]
+    pub const fn with_swing(mut self, swing: Swing) -> Self {
+        self.swing = swing;
+        self
+    }
+
+    /// Gets the number of beats per measure, if the clicks are grouped in measures.
+    #[must_use]
+    pub const fn beats_per_measure(&self) -> Option<u32> {
+        self.beats_per_measure
+    }
+
+    /// Gets the tempo clicks are currently being scheduled at.
+    #[must_use]
+    pub const fn bpm(&self) -> f64 {
+        self.bpm
+    }
+
+    /// Changes the tempo without resetting the beat count.
+    ///
+    /// The change is applied at the next click matching `at`; that click keeps the
+    /// position given by the old tempo and the spacing after it follows the new one.
+    /// A later call replaces a change that hasn't taken effect yet, and a running
+    /// tempo ramp is cancelled once the change applies.
+    pub const fn change_bpm(&mut self, bpm: f64, at: ChangeAt) {
+        self.pending_tempo = Some((PendingTempo::Bpm(bpm), at));
+    }
+
+    /// Starts a tempo ramp at the next click matching `at`.
+    ///
+    /// The ramp's tempo is evaluated at every click, so the spacing between clicks
+    /// changes smoothly. Once the ramp is over the scheduler stays at its target tempo.
+    pub const fn start_ramp(&mut self, ramp: TempoRamp, at: ChangeAt) {
+        self.pending_tempo = Some((PendingTempo::Ramp(ramp), at));
+    }
+
+    /// Gets the number of clicks per beat currently being scheduled.
+    #[must_use]
+    pub const fn subdivisions(&self) -> u32 {
+        self.subdivisions
+    }
+
+    /// Changes the number of clicks per beat without resetting the beat count.
+    ///
+    /// The change is applied at the next main beat matching `at` (`Immediately` waits for
+    /// the next main beat too, so no beat is split unevenly). A later call replaces a
+    /// change that hasn't taken effect yet.
+    pub const fn change_subdivisions(&mut self, subdivisions: u32, at: ChangeAt) {
+        let subdivisions = if subdivisions == 0 { 1 } else { subdivisions };
+        self.pending_subdivisions = Some((subdivisions, at));
+    }
+
+    /// Checks whether a tempo ramp is currently running.
+    #[must_use]
+    pub const fn is_ramping(&self) -> bool {
+        self.ramp.is_some()
+    }
+
+    /// Gets the sample rate the scheduler places clicks for.
+    #[must_use]
+    pub const fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Gets the frame of the next click without advancing the scheduler.
+    #[must_use]
+    pub fn peek_frame(&self) -> u64 {
+        if self.group_starts_only {
+            // Silent beats may lie in between, and tempo changes can still apply on them
+            self.clone().next_click().frame
+        } else {
+            to_frame(self.next_position() + self.swing_delay(self.next_index))
+        }
+    }
+
+    /// How far the swing pushes back click number `index`, in frames.
+    fn swing_delay(&self, index: u64) -> f64 {
+        if !self.subdivisions.is_multiple_of(2) || index.is_multiple_of(2) {
+            return 0.0;
+        }
+        click_offset(1, self.bpm, self.subdivisions, self.sample_rate) * self.swing.offbeat_delay()
+    }
+
+    /// Exact frame position of the next click.
+    fn next_position(&self) -> f64 {
+        self.segment_origin
+            + click_offset(
+                self.next_index - self.segment_start,
+                self.bpm,
+                self.subdivisions,
+                self.sample_rate,
+            )
+    }
+
+    /// Returns the next click and advances the scheduler.
+    pub fn next_click(&mut self) -> ScheduledClick {
+        loop {
+            if let Some(click) = self.next_position_click() {
+                return click;
+            }
+        }
+    }
+
+    /// Advances past the next click position, returning the click played there unless it is silent.
+    fn next_position_click(&mut self) -> Option<ScheduledClick> {
+        let position = self.next_position();
+        if let Some((subdivisions, at)) = self.pending_subdivisions
+            && self.next_index.is_multiple_of(u64::from(self.subdivisions))
+        {
+            let beat = self.next_index / u64::from(self.subdivisions);
+            if at != ChangeAt::NextBar || self.is_bar_line(beat) {
+                // Renumber the clicks so the beat count carries on at the new subdivision
+                self.pending_subdivisions = None;
+                self.subdivisions = subdivisions;
+                self.next_index = beat * u64::from(subdivisions);
+                self.start_segment(self.bpm, position);
+            }
+        }
+
+        let beat = self.next_index / u64::from(self.subdivisions);
+        #[allow(clippy::cast_possible_truncation)]
+        let subdivision = (self.next_index % u64::from(self.subdivisions)) as u32;
+
+        let is_bar_start = subdivision == 0 && self.is_bar_line(beat);
+        let is_accent = is_bar_start && self.beats_per_measure.is_some();
+        let is_group_start = self.grouping.is_none_or(|grouping| {
+            let beats = u64::from(grouping.notes().max(1));
+            u32::try_from(beat % beats).is_ok_and(|note| grouping.is_group_start(note))
+        });
+
+        if let Some((change, at)) = self.pending_tempo {
+            let applies = match at {
+                ChangeAt::Immediately => true,
+                ChangeAt::NextBeat => subdivision == 0,
+                ChangeAt::NextBar => is_bar_start,
+            };
+            if applies {
+                self.pending_tempo = None;
+                match change {
+                    PendingTempo::Bpm(bpm) => {
+                        self.ramp = None;
+                        self.start_segment(bpm, position);
+                    }
+                    PendingTempo::Ramp(ramp) => {
+                        self.ramp = Some(ActiveRamp {
+                            ramp,
+                            start_beat: self.beat_position(),
+                            start_position: position,
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(active) = self.ramp {
+            let progress = self.ramp_progress(&active, position);
+            if progress >= 1.0 {
+                self.ramp = None;
+            }
+            self.start_segment(active.ramp.bpm_at(progress), position);
+        }
+
+        let kind = if is_accent {
+            ClickKind::Accent
+        } else if self.group_starts_only && !(is_group_start && subdivision == 0) {
+            self.next_index += 1;
+            return None;
+        } else if subdivision != 0 || !is_group_start {
+            ClickKind::Subdivision
+        } else if self.grouping.is_some() {
+            ClickKind::SecondaryAccent
+        } else {
+            ClickKind::Regular
+        };
+
+        let frame = to_frame(position + self.swing_delay(self.next_index));
+        self.next_index += 1;
+
+        Some(ScheduledClick {
+            frame,
+            beat,
+            subdivision,
+            kind,
+        })
+    }
+}
+
+impl ClickScheduler {
+    /// Starts a new constant-tempo segment at the next click, which lies at `position`.
+    const fn start_segment(&mut self, bpm: f64, position: f64) {
+        self.bpm = bpm;
+        self.segment_origin = position;
+        self.segment_start = self.next_index;
+    }
+
+    /// Checks whether a beat starts a measure (every beat does when there are no measures).
+    fn is_bar_line(&self, beat: u64) -> bool {
+        self.beats_per_measure
+            .is_none_or(|beats| beat.is_multiple_of(u64::from(beats.max(1))))
+    }
+
+    /// Position of the next click in beats, which subdivision changes leave intact.
+    fn beat_position(&self) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let index = self.next_index as f64;
+        index / f64::from(self.subdivisions)
+    }
+
+    /// How far through a ramp the next click (at `position`) is, from 0.0 to 1.0.
+    fn ramp_progress(&self, active: &ActiveRamp, position: f64) -> f64 {
+        let beats = self.beat_position() - active.start_beat;
+
+        let progress = match active.ramp.length {
+            RampLength::Beats(beats_total) => beats / f64::from(beats_total),
+            RampLength::Bars(bars_total) => {
+                let beats_per_bar = f64::from(self.beats_per_measure.unwrap_or(1).max(1));
+                beats / (beats_per_bar * f64::from(bars_total))
+            }
+            RampLength::Seconds(seconds) => {
+                (position - active.start_position) / (seconds * f64::from(self.sample_rate))
+            }
+        };
+
+        // A zero-length ramp jumps straight to its target
+        if progress.is_nan() {
+            1.0
+        } else {
+            progress.min(1.0)
+        }
+    }
+}
+
+impl Iterator for ClickScheduler {
+    type Item = ScheduledClick;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_click())
+    }
+}
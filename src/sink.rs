@@ -0,0 +1,202 @@
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::engine::AudioEngine;
+use crate::voice::{Voice, VoiceMixer};
+
+/// Somewhere metronome clicks and tones can be played.
+///
+/// A sink owns an audio clock counted in frames. Voices are submitted with an
+/// optional start frame on that clock, and the sink is responsible for mixing
+/// (or otherwise handling) them. [`AudioEngine`] plays through a cpal device,
+/// [`NullSink`] discards everything, and [`CapturingSink`] records what was played.
+pub trait AudioSink: Send + Sync {
+    /// Gets the sample rate of the sink's clock in Hz.
+    fn sample_rate(&self) -> u32;
+
+    /// Gets the number of output channels.
+    fn channels(&self) -> u16;
+
+    /// Gets the number of frames the sink has played so far.
+    fn frames_played(&self) -> u64;
+
+    /// Submits a voice to be played.
+    fn play(&self, voice: Voice);
+
+    /// Estimates how long after a frame is played on the sink's clock it becomes audible.
+    ///
+    /// Sinks that don't play to a device have no latency.
+    fn output_latency(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+impl AudioSink for AudioEngine {
+    fn sample_rate(&self) -> u32 {
+        Self::sample_rate(self)
+    }
+
+    fn channels(&self) -> u16 {
+        Self::channels(self)
+    }
+
+    fn frames_played(&self) -> u64 {
+        Self::frames_played(self)
+    }
+
+    fn play(&self, voice: Voice) {
+        Self::play(self, voice);
+    }
+
+    fn output_latency(&self) -> Duration {
+        Self::output_latency(self)
+    }
+}
+
+/// A clock that advances in real time, like an audio device would.
+#[derive(Debug)]
+struct RealTimeClock {
+    started: Instant,
+    sample_rate: u32,
+}
+
+impl RealTimeClock {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            started: Instant::now(),
+            sample_rate,
+        }
+    }
+
+    fn frames(&self) -> u64 {
+        let elapsed = self.started.elapsed();
+        elapsed.as_secs() * u64::from(self.sample_rate)
+            + u64::from(elapsed.subsec_nanos()) * u64::from(self.sample_rate) / 1_000_000_000
+    }
+}
+
+/// A sink that discards all audio while keeping a real-time clock.
+///
+/// Useful for running a metronome where no audio device is available, such as on CI machines.
+#[derive(Debug)]
+pub struct NullSink {
+    clock: RealTimeClock,
+    channels: u16,
+}
+
+impl NullSink {
+    /// Creates a null sink whose clock starts now.
+    #[must_use]
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            clock: RealTimeClock::new(sample_rate),
+            channels,
+        }
+    }
+}
+
+impl Default for NullSink {
+    fn default() -> Self {
+        Self::new(48_000, 2)
+    }
+}
+
+impl AudioSink for NullSink {
+    fn sample_rate(&self) -> u32 {
+        self.clock.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn frames_played(&self) -> u64 {
+        self.clock.frames()
+    }
+
+    fn play(&self, _voice: Voice) {}
+}
+
+/// A sink that records every voice played on it, keeping a real-time clock.
+///
+/// Voices are stored with their start frame resolved, so tests can assert on
+/// exactly what was played and when, or render the captured audio with
+/// [`CapturingSink::samples`].
+#[derive(Debug)]
+pub struct CapturingSink {
+    clock: RealTimeClock,
+    channels: u16,
+    voices: Mutex<Vec<Voice>>,
+}
+
+impl CapturingSink {
+    /// Creates a capturing sink whose clock starts now.
+    #[must_use]
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            clock: RealTimeClock::new(sample_rate),
+            channels,
+            voices: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the voices played so far, in the order they were submitted.
+    ///
+    /// Every returned voice has its `start_frame` set.
+    #[must_use]
+    pub fn voices(&self) -> Vec<Voice> {
+        self.voices
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Forgets all voices played so far.
+    pub fn clear(&self) {
+        self.voices
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Mixes the captured voices into the first `frames` mono frames of the sink's clock.
+    #[must_use]
+    pub fn samples(&self, frames: usize) -> Vec<f32> {
+        let mut mixer = VoiceMixer::new(self.clock.sample_rate);
+        for voice in self.voices() {
+            mixer.add(voice, 0);
+        }
+
+        let mut output = vec![0.0; frames];
+        mixer.render(&mut output, 0);
+        output
+    }
+}
+
+impl Default for CapturingSink {
+    fn default() -> Self {
+        Self::new(48_000, 2)
+    }
+}
+
+impl AudioSink for CapturingSink {
+    fn sample_rate(&self) -> u32 {
+        self.clock.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn frames_played(&self) -> u64 {
+        self.clock.frames()
+    }
+
+    fn play(&self, voice: Voice) {
+        let start = voice.start_frame.unwrap_or_else(|| self.clock.frames());
+        self.voices
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(voice.at_frame(start));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::error::{MetronomeError, validate_bpm};
+
+/// Raises a metronome's tempo step by step as bars go by, for building up speed.
+///
+/// The trainer starts at `start_bpm` and adds `step_bpm` every `every_bars` bars until
+/// it reaches `max_bpm`, where it stays. A cycling trainer instead drops back to
+/// `start_bpm` after the bars at `max_bpm` and climbs again. Tempo changes land on bar
+/// lines of a running metronome, so the click never stops and bars keep counting.
+///
+/// Bars are counted from the start of the main click, after any count-in.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, Metronome, SpeedTrainer};
+///
+/// // 80 BPM, 5 BPM faster every 2 bars, up to 90 BPM, then from the top again
+/// let trainer = SpeedTrainer::new(80.0, 5.0, 2, 90.0).with_cycles(true);
+/// let tempos: Vec<f64> = (0..8).map(|bar| trainer.bpm_at_bar(bar)).collect();
+/// assert_eq!(tempos, [80.0, 80.0, 85.0, 85.0, 90.0, 90.0, 80.0, 80.0]);
+///
+/// let mut metronome = Metronome::offline(80.0, Some(4), AccentConfig::default())?;
+/// metronome.set_speed_trainer(Some(trainer));
+/// # Ok::<(), metronome_rs::MetronomeError>(())
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpeedTrainer {
+    /// Tempo of the first bars
+    pub start_bpm: f64,
+    /// Tempo added at every step
+    pub step_bpm: f64,
+    /// Bars played at each tempo (beats when the metronome has no measures)
+    pub every_bars: u32,
+    /// Highest tempo; the last step stops here even if it is smaller than `step_bpm`
+    pub max_bpm: f64,
+    /// Whether to drop back to `start_bpm` after the bars at `max_bpm`
+    pub cycles: bool,
+}
+
+impl SpeedTrainer {
+    /// Most tempos a trainer can step through from `start_bpm` to `max_bpm`
+    pub const MAX_TEMPOS: u64 = 10_000;
+
+    /// Creates a trainer that adds `step_bpm` every `every_bars` bars, from `start_bpm`
+    /// up to `max_bpm`.
+    #[must_use]
+    pub const fn new(start_bpm: f64, step_bpm: f64, every_bars: u32, max_bpm: f64) -> Self {
+        Self {
+            start_bpm,
+            step_bpm,
+            every_bars,
+            max_bpm,
+            cycles: false,
+        }
+    }
+
+    /// Returns a copy of this trainer that steps up after `repetitions` plays of a loop
+    /// of `loop_bars` bars.
+    #[must_use]
+    pub const fn with_loop(mut self, loop_bars: u32, repetitions: u32) -> Self {
+        self.every_bars = loop_bars.saturating_mul(repetitions);
+        self
+    }
+
+    /// Returns a copy of this trainer that drops back to the starting tempo after
+    /// reaching the top, or stays at the top when `cycles` is false.
+    #[must_use]
+    pub const fn with_cycles(mut self, cycles: bool) -> Self {
+        self.cycles = cycles;
+        self
+    }
+
+    /// Gets the number of tempos played on the way from `start_bpm` to `max_bpm`.
+    #[must_use]
+    pub fn tempo_count(&self) -> u64 {
+        let steps = ((self.max_bpm - self.start_bpm) / self.step_bpm - 1e-9).ceil();
+        // Checked by `validate` to be a small positive number of steps; the cast saturates
+        // on a trainer that wasn't validated
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let steps = steps.max(0.0) as u64;
+        steps.checked_add(1).unwrap_or(steps)
+    }
+
+    /// Gets the tempo of a bar, counted from 0 at the start of the main click.
+    #[must_use]
+    pub fn bpm_at_bar(&self, bar: u64) -> f64 {
+        let mut step = bar / u64::from(self.every_bars.max(1));
+        if self.cycles {
+            step %= self.tempo_count();
+        }
+        // Steps are far below 2^53, so they convert exactly
+        #[allow(clippy::cast_precision_loss)]
+        let bpm = self.step_bpm.mul_add(step as f64, self.start_bpm);
+        bpm.min(self.max_bpm)
+    }
+
+    /// Checks that the trainer can be played.
+    pub(crate) fn validate(&self) -> Result<(), MetronomeError> {
+        validate_bpm(self.start_bpm)?;
+        validate_bpm(self.max_bpm)?;
+        if self.every_bars > 0
+            && self.step_bpm.is_finite()
+            && self.step_bpm > 0.0
+            && self.max_bpm >= self.start_bpm
+            && self.tempo_count() <= Self::MAX_TEMPOS
+        {
+            Ok(())
+        } else {
+            Err(MetronomeError::InvalidConfig(format!(
+                "speed trainer needs at least one bar per tempo, a positive step, a top tempo \
+                 no lower than the start and at most {} tempos, got {self:?}",
+                Self::MAX_TEMPOS
+            )))
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError, mpsc};
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use crate::error::MetronomeError;
+use crate::event::{BeatEvent, WakerSlot};
+use crate::metronome::Metronome;
+
+/// An async stream of the events of every click a metronome queues.
+///
+/// Created with [`Metronome::beat_stream`]. Like the channel returned by
+/// [`Metronome::events`], events arrive as soon as their click is queued, ahead of the
+/// time they are heard, and the stream ends when the metronome stops.
+///
+/// Requires the `async` feature.
+pub struct BeatStream {
+    receiver: mpsc::Receiver<BeatEvent>,
+    waker: Arc<WakerSlot>,
+}
+
+impl Stream for BeatStream {
+    type Item = BeatEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<BeatEvent>> {
+        // Register before checking the channel, so an event sent in between still wakes the task
+        *self.waker.lock().unwrap_or_else(PoisonError::into_inner) = Some(cx.waker().clone());
+        match self.receiver.try_recv() {
+            Ok(event) => Poll::Ready(Some(event)),
+            Err(mpsc::TryRecvError::Empty) => Poll::Pending,
+            Err(mpsc::TryRecvError::Disconnected) => Poll::Ready(None),
+        }
+    }
+}
+
+impl Metronome {
+    /// Opens an async stream of events for every click queued from now on.
+    ///
+    /// Requires the `async` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_util::StreamExt;
+    /// use metronome_rs::{Metronome, NullSink};
+    /// use std::sync::Arc;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build()?.block_on(async {
+    /// let metronome = Metronome::with_sink(240.0, Some(4), Arc::new(NullSink::default()))?;
+    /// let mut beats = metronome.beat_stream();
+    /// metronome.start_for_bars(1)?;
+    ///
+    /// while let Some(event) = beats.next().await {
+    ///     println!("bar {} beat {}", event.bar, event.beat_in_bar);
+    /// }
+    /// # Ok::<(), metronome_rs::MetronomeError>(())
+    /// # })?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use]
+    pub fn beat_stream(&self) -> BeatStream {
+        let waker = Arc::new(Mutex::new(None));
+        BeatStream {
+            receiver: self
+                .listeners
+                .subscribe_waking(self.listening_run(), Arc::clone(&waker)),
+            waker,
+        }
+    }
+
+    /// Starts the metronome like [`Metronome::start_for_bars`] and completes once the
+    /// last of `bars` bars has played.
+    ///
+    /// Requires the `async` feature.
+    ///
+    /// # Cancellation
+    ///
+    /// Dropping the future before it completes, for example when it loses a `select!`
+    /// against a timeout, stops the metronome. A metronome started again since is left
+    /// playing.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Metronome::start`], or [`MetronomeError::Stopped`] if
+    /// the metronome is stopped or started again before the last bar has played.
+    pub async fn run_for_bars(&self, bars: u64) -> Result<(), MetronomeError> {
+        let waker = Arc::new(Mutex::new(None));
+        let (run, receiver) = self.start_limited(Some(bars), |run| {
+            (
+                run,
+                self.listeners.subscribe_waking(run, Arc::clone(&waker)),
+            )
+        })?;
+        let _stop = StopRun {
+            metronome: self,
+            run,
+        };
+        let mut stream = BeatStream { receiver, waker };
+
+        // The run's channel closes when its thread exits, whether all bars were played or not
+        let last_beat = bars.saturating_mul(self.beats_per_bar()).checked_sub(1);
+        let mut finished = last_beat.is_none();
+        poll_fn(|cx| {
+            loop {
+                match Pin::new(&mut stream).poll_next(cx) {
+                    Poll::Ready(Some(event)) => {
+                        finished |= !event.count_in && Some(event.beat) >= last_beat;
+                    }
+                    Poll::Ready(None) => return Poll::Ready(()),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        })
+        .await;
+        if finished {
+            Ok(())
+        } else {
+            Err(MetronomeError::Stopped)
+        }
+    }
+}
+
+/// Stops a run of a metronome when dropped, so a cancelled [`Metronome::run_for_bars`]
+/// doesn't leave it playing.
+struct StopRun<'a> {
+    metronome: &'a Metronome,
+    run: u64,
+}
+
+impl Drop for StopRun<'_> {
+    fn drop(&mut self) {
+        self.metronome.stop_run(self.run);
+    }
+}
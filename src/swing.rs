@@ -0,0 +1,92 @@
+use std::fmt;
+
+use crate::error::MetronomeError;
+
+/// How unevenly pairs of subdivisions are played, from straight to a hard shuffle.
+///
+/// Swing is given as the share of each pair the first note takes: 50% plays the pair
+/// straight, 66.7% plays it like the outer notes of a triplet, and 75% like a dotted
+/// eighth and a sixteenth. Only the second note of every pair moves, so main beats
+/// stay on the grid.
+///
+/// Swing applies when a beat is split into an even number of clicks, such as eighth or
+/// sixteenth note subdivisions, and is ignored otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, Metronome, Swing};
+///
+/// // Swung eighths at 160 BPM
+/// let config = AccentConfig::default().set_subdivisions(2);
+/// let mut metronome = Metronome::offline(160.0, Some(4), config)?;
+/// metronome.set_swing(Swing::TRIPLET);
+/// assert_eq!(metronome.swing().to_string(), "67%");
+///
+/// // Or as a percentage
+/// metronome.set_swing(Swing::percent(60.0));
+/// # Ok::<(), metronome_rs::MetronomeError>(())
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Swing {
+    ratio: f64,
+}
+
+impl Swing {
+    /// Even subdivisions (50%)
+    pub const STRAIGHT: Self = Self { ratio: 0.5 };
+    /// A lazy, laid-back feel (58%), common at fast tempos
+    pub const LIGHT: Self = Self { ratio: 0.58 };
+    /// Triplet swing (66.7%), the classic jazz and shuffle feel
+    pub const TRIPLET: Self = Self { ratio: 2.0 / 3.0 };
+    /// Hard swing (75%), played like a dotted eighth and a sixteenth
+    pub const HARD: Self = Self { ratio: 0.75 };
+
+    /// Creates a swing from the percentage of each pair the first note takes (50 = straight).
+    #[must_use]
+    pub fn percent(percent: f64) -> Self {
+        Self {
+            ratio: percent / 100.0,
+        }
+    }
+
+    /// Gets the percentage of each pair the first note takes.
+    #[must_use]
+    pub fn as_percent(&self) -> f64 {
+        self.ratio * 100.0
+    }
+
+    /// Checks whether pairs are played evenly.
+    #[must_use]
+    pub fn is_straight(&self) -> bool {
+        (self.ratio - 0.5).abs() < f64::EPSILON
+    }
+
+    /// How far the second note of a pair is pushed back, in subdivisions.
+    pub(crate) const fn offbeat_delay(self) -> f64 {
+        self.ratio.mul_add(2.0, -1.0)
+    }
+
+    /// Checks that the swing can be played.
+    pub(crate) fn validate(self) -> Result<(), MetronomeError> {
+        if (0.5..1.0).contains(&self.ratio) {
+            Ok(())
+        } else {
+            Err(MetronomeError::InvalidConfig(format!(
+                "swing must be at least 50% and below 100%, got {self}"
+            )))
+        }
+    }
+}
+
+impl Default for Swing {
+    fn default() -> Self {
+        Self::STRAIGHT
+    }
+}
+
+impl fmt::Display for Swing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.0}%", self.as_percent())
+    }
+}
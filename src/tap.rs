@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::error::{MetronomeError, validate_bpm};
+use crate::metronome::Metronome;
+
+/// Estimates a tempo from taps, such as presses of a "tap" button.
+///
+/// Each tap after the first adds the time since the previous tap to a sliding window
+/// of intervals. The estimate averages the intervals in the window that are within a
+/// tolerance of their median, so a single early or late tap doesn't pull the tempo off.
+/// When no tap arrives for longer than the timeout, the user is assumed to have stopped
+/// tapping: the estimate is dropped and the next tap starts a new one. A tap coming sooner than
+/// [`TapTempo::MIN_INTERVAL`] after the previous one is taken for a bounce and ignored.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{AccentConfig, Metronome, TapTempo};
+/// use std::time::{Duration, Instant};
+///
+/// let mut tap = TapTempo::new();
+/// let start = Instant::now();
+/// for beat in 0..4 {
+///     tap.tap_at(start + Duration::from_millis(beat * 500));
+/// }
+/// assert_eq!(tap.bpm(), Some(120.0));
+///
+/// let metronome = Metronome::offline(90.0, Some(4), AccentConfig::default())?;
+/// tap.apply_to(&metronome)?;
+/// assert_eq!(metronome.bpm(), 120.0);
+/// # Ok::<(), metronome_rs::MetronomeError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TapTempo {
+    window: usize,
+    tolerance: f64,
+    timeout: Duration,
+    last_tap: Option<Instant>,
+    intervals: VecDeque<Duration>,
+}
+
+impl TapTempo {
+    /// Default number of intervals averaged
+    pub const DEFAULT_WINDOW: usize = 8;
+    /// Default largest deviation from the median interval that is still averaged, as a fraction
+    pub const DEFAULT_TOLERANCE: f64 = 0.2;
+    /// Default pause after which tapping starts over
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+    /// Shortest interval between taps that counts, the beat length at 400 BPM
+    pub const MIN_INTERVAL: Duration = Duration::from_millis(150);
+
+    /// Creates a tap tempo estimator with the default window, tolerance and timeout.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            window: Self::DEFAULT_WINDOW,
+            tolerance: Self::DEFAULT_TOLERANCE,
+            timeout: Self::DEFAULT_TIMEOUT,
+            last_tap: None,
+            intervals: VecDeque::new(),
+        }
+    }
+
+    /// Returns a copy of this estimator averaging over the last `window` intervals (at least 1).
+    #[must_use]
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self.trim();
+        self
+    }
+
+    /// Returns a copy of this estimator that ignores intervals deviating from the median
+    /// by more than `tolerance`, as a fraction of the median (0.2 = 20%).
+    #[must_use]
+    pub const fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance.max(0.0);
+        self
+    }
+
+    /// Returns a copy of this estimator that starts over after a pause longer than `timeout`.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Records a tap at the current time and returns the new estimate.
+    pub fn tap(&mut self) -> Option<f64> {
+        self.tap_at(Instant::now())
+    }
+
+    /// Records a tap at the given time and returns the new estimate.
+    ///
+    /// A tap more than the timeout after the previous one, or before it, starts a new
+    /// estimate. A tap less than [`TapTempo::MIN_INTERVAL`] after the previous one is ignored.
+    pub fn tap_at(&mut self, at: Instant) -> Option<f64> {
+        match self
+            .last_tap
+            .and_then(|last| at.checked_duration_since(last))
+        {
+            Some(interval) if interval < Self::MIN_INTERVAL => return self.estimate(),
+            Some(interval) if interval <= self.timeout => {
+                self.intervals.push_back(interval);
+                self.trim();
+            }
+            _ => self.intervals.clear(),
+        }
+        self.last_tap = Some(at);
+        self.estimate()
+    }
+
+    /// Gets the estimated tempo, or `None` until two taps have been recorded, once the
+    /// timeout has passed since the last tap, or if the taps don't give a playable tempo.
+    #[must_use]
+    pub fn bpm(&self) -> Option<f64> {
+        if self.is_tapping() {
+            self.estimate()
+        } else {
+            None
+        }
+    }
+
+    /// Sets the tempo of `metronome` to the estimate.
+    ///
+    /// A playing metronome changes tempo from its next click.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MetronomeError::InvalidConfig`] if there is no estimate, as described for
+    /// [`TapTempo::bpm`]; the metronome's tempo is left unchanged.
+    pub fn apply_to(&self, metronome: &Metronome) -> Result<(), MetronomeError> {
+        let bpm = self.bpm().ok_or_else(|| {
+            MetronomeError::InvalidConfig("no tempo has been tapped recently".to_string())
+        })?;
+        metronome.set_bpm(bpm)
+    }
+
+    /// Gets the estimate from the taps in the window, however long ago they were.
+    fn estimate(&self) -> Option<f64> {
+        let median = self.median()?;
+        // A huge tolerance lets every interval through
+        let limit = Duration::try_from_secs_f64(median.as_secs_f64() * self.tolerance)
+            .unwrap_or(Duration::MAX);
+        let (sum, count) = self
+            .intervals
+            .iter()
+            .filter(|interval| interval.abs_diff(median) <= limit)
+            .fold((Duration::ZERO, 0_u32), |(sum, count), interval| {
+                (sum + *interval, count + 1)
+            });
+
+        // The median is always within tolerance of itself, but two middle intervals may not be
+        let mean = if count == 0 { median } else { sum / count };
+        let bpm = 60.0 / mean.as_secs_f64();
+        validate_bpm(bpm).ok().map(|()| bpm)
+    }
+
+    /// Gets the number of taps in the current estimate.
+    #[must_use]
+    pub fn tap_count(&self) -> usize {
+        self.last_tap.map_or(0, |_| self.intervals.len() + 1)
+    }
+
+    /// Checks whether the user is still tapping, i.e. the last tap was no longer ago
+    /// than the timeout.
+    #[must_use]
+    pub fn is_tapping(&self) -> bool {
+        self.last_tap
+            .is_some_and(|last| last.elapsed() <= self.timeout)
+    }
+
+    /// Forgets all taps.
+    pub fn reset(&mut self) {
+        self.last_tap = None;
+        self.intervals.clear();
+    }
+
+    /// Gets the median of the intervals in the window.
+    fn median(&self) -> Option<Duration> {
+        let mut sorted: Vec<Duration> = self.intervals.iter().copied().collect();
+        sorted.sort_unstable();
+        let middle = sorted.len() / 2;
+        match sorted.len() {
+            0 => None,
+            len if len % 2 == 0 => Some((sorted[middle - 1] + sorted[middle]) / 2),
+            _ => Some(sorted[middle]),
+        }
+    }
+
+    /// Drops the oldest intervals beyond the window.
+    fn trim(&mut self) {
+        while self.intervals.len() > self.window {
+            self.intervals.pop_front();
+        }
+    }
+}
+
+impl Default for TapTempo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,19 +1,91 @@
 #[cfg(test)]
-use crate::{AccentConfig, Metronome, stop_global_metronome};
+use crate::{AccentConfig, CapturingSink, Metronome, NullSink, stop_global_metronome};
+#[cfg(test)]
+use std::sync::Arc;
 #[cfg(test)]
 use std::thread;
 #[cfg(test)]
 use std::time::Duration;
 
 #[test]
+#[ignore = "requires an audio output device"]
 fn test_beep() {
     println!("Ensure you hear a beep sound");
     crate::beep().expect("Failed to play beep");
 }
 
 #[test]
-fn test_metronome_creation() {
+fn test_invalid_tempo_errors() {
+    use crate::{
+        BitDepth, ChangeAt, MetronomeError, RampCurve, RampLength, RenderLength, TempoRamp,
+        render_to_wav,
+    };
+
+    // Every constructor checks the tempo
+    let result = Metronome::with_sink(0.0, Some(4), Arc::new(NullSink::default()));
+    assert!(matches!(result, Err(MetronomeError::InvalidConfig(_))));
+    let result = Metronome::new(-1.0, Some(4));
+    assert!(matches!(result, Err(MetronomeError::InvalidConfig(_))));
+    let result = Metronome::offline(f64::NAN, Some(4), AccentConfig::default());
+    assert!(matches!(result, Err(MetronomeError::InvalidConfig(_))));
+
+    let metronome = Metronome::with_sink(120.0, Some(0), Arc::new(NullSink::default())).unwrap();
+    let result = metronome.start();
+    assert!(matches!(result, Err(MetronomeError::InvalidConfig(_))));
+    assert!(!metronome.is_playing());
+
+    // Tempo changes are checked before they can reach a playing metronome
+    let metronome = Metronome::with_sink(120.0, Some(4), Arc::new(NullSink::default())).unwrap();
+    assert!(metronome.set_bpm(-10.0).is_err());
+    assert!(metronome.set_bpm_at(0.0, ChangeAt::NextBar).is_err());
+    let ramp = TempoRamp::new(0.0, 120.0, RampLength::Bars(2)).with_curve(RampCurve::Exponential);
+    assert!(metronome.ramp_tempo(ramp, ChangeAt::Immediately).is_err());
+    assert!((metronome.bpm() - 120.0).abs() < f64::EPSILON);
+
+    let metronome = Metronome::offline(120.0, Some(0), AccentConfig::default()).unwrap();
+    let mut wav = Vec::new();
+    let result = render_to_wav(
+        &metronome,
+        RenderLength::Bars(1),
+        48_000,
+        BitDepth::Int16,
+        &mut wav,
+    );
+    let error = result.expect_err("a bar of no beats should be rejected");
+    assert!(matches!(error, MetronomeError::InvalidConfig(_)));
+    assert!(error.to_string().starts_with("invalid configuration"));
+    assert!(wav.is_empty());
+
+    let error = MetronomeError::from(cpal::BuildStreamError::DeviceNotAvailable);
+    assert!(matches!(error, MetronomeError::NoOutputDevice));
+}
+
+#[test]
+fn test_tone_on_capturing_sink() {
+    use crate::{WaveType, play_tone_on_sink};
+
+    let sink = CapturingSink::new(48_000, 2);
+    play_tone_on_sink(&sink, 440.0, 50, WaveType::Triangle, 0.8);
+
+    let voices = sink.voices();
+    assert_eq!(voices.len(), 1);
+    assert!((voices[0].frequency - 440.0).abs() < f32::EPSILON);
+    assert_eq!(voices[0].wave_type, WaveType::Triangle);
+    assert!(voices[0].start_frame.is_some());
+}
+
+#[test]
+#[ignore = "requires an audio output device"]
+fn test_metronome_on_default_device() {
     let metronome = Metronome::new(120.0, Some(4)).expect("Failed to create metronome");
+    metronome.start().expect("Failed to start metronome");
+    thread::sleep(Duration::from_millis(600));
+    metronome.stop();
+}
+
+#[test]
+fn test_metronome_creation() {
+    let metronome = Metronome::with_sink(120.0, Some(4), Arc::new(NullSink::default())).unwrap();
     assert!((metronome.bpm() - 120.0).abs() < f64::EPSILON);
     assert_eq!(metronome.beats_per_measure(), Some(4));
     assert!(!metronome.is_playing());
@@ -21,8 +93,8 @@ fn test_metronome_creation() {
 
 #[test]
 fn test_metronome_simple() {
-    println!("Testing simple metronome (120 BPM, no measures) - should hear 3 beats");
-    let metronome = Metronome::new(120.0, None).expect("Failed to create metronome");
+    println!("Testing simple metronome (120 BPM, no measures) - should play 3 beats");
+    let metronome = Metronome::with_sink(120.0, None, Arc::new(NullSink::default())).unwrap();
 
     metronome.start().expect("Failed to start metronome");
     assert!(metronome.is_playing());
@@ -40,22 +112,42 @@ fn test_metronome_with_measures() {
     println!(
         "Testing metronome with measures (120 BPM, 4 beats per measure) - first beat should be accented"
     );
-    let metronome = Metronome::new(120.0, Some(4)).expect("Failed to create metronome");
+    let sink = Arc::new(CapturingSink::new(48_000, 2));
+    let metronome = Metronome::with_sink(120.0, Some(4), sink.clone()).unwrap();
 
     metronome.start().expect("Failed to start metronome");
 
     // Let it play for about 2 measures (4 seconds at 120 BPM)
+    #[allow(clippy::duration_suboptimal_units)]
     thread::sleep(Duration::from_millis(4000));
 
     metronome.stop();
     thread::sleep(Duration::from_millis(100));
     assert!(!metronome.is_playing());
+
+    // Every fourth beat uses the accent sound, and beats are exactly 0.5s apart
+    let voices = sink.voices();
+    assert!(voices.len() >= 8);
+    let accent = AccentConfig::default();
+    for (beat, voice) in voices.iter().enumerate() {
+        let expected = if beat % 4 == 0 {
+            accent.accent_frequency
+        } else {
+            accent.regular_frequency
+        };
+        assert!((voice.frequency - expected).abs() < f32::EPSILON);
+    }
+    for pair in voices.windows(2) {
+        assert_eq!(
+            pair[1].start_frame.unwrap() - pair[0].start_frame.unwrap(),
+            24_000
+        );
+    }
 }
 
 #[test]
 fn test_accent_configuration() {
-    let metronome = Metronome::new_with_accent(120.0, Some(4), AccentConfig::strong())
-        .expect("Failed to create metronome with strong accent");
+    let metronome = Metronome::offline(120.0, Some(4), AccentConfig::strong()).unwrap();
 
     // Test that accent config is properly set
     assert!((metronome.accent_config().accent_frequency - 1760.0).abs() < f32::EPSILON);
@@ -86,8 +178,8 @@ fn test_global_metronome_singleton() {
     stop_global_metronome();
     thread::sleep(Duration::from_millis(500)); // Increased wait time
 
-    let metronome1 = Metronome::new(120.0, None).expect("Failed to create metronome 1");
-    let metronome2 = Metronome::new(140.0, Some(3)).expect("Failed to create metronome 2");
+    let metronome1 = Metronome::with_sink(120.0, None, Arc::new(NullSink::default())).unwrap();
+    let metronome2 = Metronome::with_sink(140.0, Some(3), Arc::new(NullSink::default())).unwrap();
 
     // Start first metronome
     metronome1.start().expect("Failed to start metronome 1");
@@ -122,7 +214,7 @@ fn test_metronome_start_stop_simple() {
     stop_global_metronome();
     thread::sleep(Duration::from_millis(100));
 
-    let metronome = Metronome::new(120.0, None).expect("Failed to create metronome");
+    let metronome = Metronome::with_sink(120.0, None, Arc::new(NullSink::default())).unwrap();
 
     // Start metronome
     metronome.start().expect("Failed to start metronome");
@@ -172,19 +264,29 @@ fn test_wave_type_configurations() {
 fn test_metronome_with_wave_types() {
     use crate::{AccentConfig, WaveType};
 
-    println!("Testing metronome with square wave type - should hear harsh digital sounds");
+    println!("Testing metronome with square wave type - should play harsh digital sounds");
     let square_config = AccentConfig::with_wave_type(WaveType::Square);
-    let metronome = Metronome::new_with_accent(120.0, Some(4), square_config)
-        .expect("Failed to create metronome with square wave");
+    let sink = Arc::new(CapturingSink::new(48_000, 2));
+    let mut metronome = Metronome::with_sink(120.0, Some(4), sink.clone()).unwrap();
+    metronome.set_accent_config(square_config);
 
     metronome.start().expect("Failed to start metronome");
     thread::sleep(Duration::from_millis(1500)); // About 3 beats
     metronome.stop();
     thread::sleep(Duration::from_millis(100));
     assert!(!metronome.is_playing());
+
+    let voices = sink.voices();
+    assert!(!voices.is_empty());
+    assert!(
+        voices
+            .iter()
+            .all(|voice| voice.wave_type == WaveType::Square)
+    );
 }
 
 #[test]
+#[ignore = "requires an audio output device"]
 fn test_high_level_helper_functions() {
     use crate::{
         start_metronome_with_time_signature, start_performance_metronome, start_practice_metronome,
@@ -226,6 +328,7 @@ fn test_high_level_helper_functions() {
 }
 
 #[test]
+#[ignore = "requires an audio output device"]
 fn test_timed_metronome_functions() {
     use crate::{AccentConfig, play_custom_metronome_for_duration, play_metronome_for_duration};
 
@@ -241,3 +344,1330 @@ fn test_timed_metronome_functions() {
 
     println!("Timed metronome functions test completed");
 }
+
+#[test]
+fn test_voice_mixer_places_voices_on_engine_clock() {
+    use crate::WaveType;
+    use crate::voice::{REAL_TIME_VOICES, Voice, VoiceMixer};
+
+    let mut mixer = VoiceMixer::new(1000);
+    // 10ms square click at 1kHz sample rate = 10 frames, starting at frame 5
+    mixer.add(Voice::new(100.0, 10, WaveType::Square, 0.5).at_frame(5), 0);
+
+    let mut buffer = vec![0.0; 20];
+    mixer.render(&mut buffer, 0);
+
+    assert!(buffer[..5].iter().all(|&s| s == 0.0));
+    assert!(
+        buffer[5..15]
+            .iter()
+            .all(|&s| (s.abs() - 0.5).abs() < f32::EPSILON)
+    );
+    assert!(buffer[15..].iter().all(|&s| s == 0.0));
+
+    // Overlapping voices are summed and limited
+    mixer.add(
+        Voice::new(100.0, 10, WaveType::Square, 1.0).at_frame(20),
+        20,
+    );
+    mixer.add(
+        Voice::new(100.0, 10, WaveType::Square, 1.0).at_frame(20),
+        20,
+    );
+    mixer.render(&mut buffer, 20);
+    assert!(
+        buffer[..10]
+            .iter()
+            .all(|&s| (s.abs() - 1.0).abs() < f32::EPSILON)
+    );
+
+    // A full real-time mixer makes room by dropping the voice that ends first
+    let mut mixer = VoiceMixer::real_time(1000);
+    mixer.add(Voice::new(100.0, 10, WaveType::Square, 0.5).at_frame(0), 0);
+    for _ in 0..REAL_TIME_VOICES {
+        mixer.add(
+            Voice::new(100.0, 10, WaveType::Square, 0.001).at_frame(10),
+            0,
+        );
+    }
+    mixer.render(&mut buffer, 0);
+    assert!(buffer[..10].iter().all(|&s| s == 0.0));
+    assert!(buffer[10..].iter().all(|&s| s != 0.0));
+}
+
+#[test]
+fn test_click_onsets_match_ideal_grid() {
+    use crate::ClickScheduler;
+
+    // 7 subdivisions at 133 BPM used to drift with millisecond truncation
+    let sample_rate = 48_000u32;
+    let scheduler = ClickScheduler::new(133.0, Some(4), 7, sample_rate);
+
+    for (index, click) in (0u64..).zip(scheduler.take(10_000)) {
+        // Ideal onset is index * 60 * sample_rate / (133 * 7) frames; check it is within half a frame
+        let ideal_numerator = u128::from(index) * 60 * u128::from(sample_rate);
+        let denominator = 133 * 7u128;
+        let error = (u128::from(click.frame) * denominator).abs_diff(ideal_numerator);
+        assert!(
+            error * 2 <= denominator,
+            "click {index} at frame {} is off the grid",
+            click.frame
+        );
+    }
+}
+
+#[test]
+fn test_click_onsets_stay_on_grid_after_an_hour() {
+    use crate::click_onset_frame;
+
+    // 133.7 BPM with triplets for one hour at 44.1kHz
+    let sample_rate = 44_100u32;
+    // 133.7 beats * 3 clicks * 60 minutes
+    let last_index = 24_066u64;
+
+    let onset = click_onset_frame(last_index, 133.7, 3, sample_rate);
+
+    // Ideal onset is index * 60 * sample_rate * 10 / (1337 * 3)
+    let ideal_numerator = u128::from(last_index) * 60 * u128::from(sample_rate) * 10;
+    let denominator = 1337 * 3u128;
+    let error = (u128::from(onset) * denominator).abs_diff(ideal_numerator);
+    assert!(error * 2 <= denominator);
+
+    // Consecutive clicks are never more than one frame away from the exact spacing
+    let exact_spacing = 60.0 * f64::from(sample_rate) / (133.7 * 3.0);
+    for index in last_index - 100..last_index {
+        let spacing = click_onset_frame(index + 1, 133.7, 3, sample_rate)
+            - click_onset_frame(index, 133.7, 3, sample_rate);
+        let spacing = f64::from(u32::try_from(spacing).unwrap());
+        assert!((spacing - exact_spacing).abs() < 1.0);
+    }
+}
+
+#[test]
+fn test_click_scheduler_kinds_and_origin() {
+    use crate::{ClickKind, ClickScheduler};
+
+    let clicks: Vec<_> = ClickScheduler::new(120.0, Some(3), 2, 1000)
+        .starting_at(250)
+        .take(8)
+        .collect();
+
+    let kinds: Vec<_> = clicks.iter().map(|click| click.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ClickKind::Accent,
+            ClickKind::Subdivision,
+            ClickKind::Regular,
+            ClickKind::Subdivision,
+            ClickKind::Regular,
+            ClickKind::Subdivision,
+            ClickKind::Accent,
+            ClickKind::Subdivision,
+        ]
+    );
+
+    // 120 BPM with eighth notes at 1kHz is one click every 250 frames
+    let frames: Vec<_> = clicks.iter().map(|click| click.frame).collect();
+    assert_eq!(frames, vec![250, 500, 750, 1000, 1250, 1500, 1750, 2000]);
+    assert_eq!(clicks[7].beat, 3);
+    assert_eq!(clicks[7].subdivision, 1);
+}
+
+#[test]
+fn test_click_scheduler_tempo_changes_keep_position() {
+    use crate::{ChangeAt, ClickKind, ClickScheduler};
+
+    // 60 BPM in 4/4 at 1kHz: one beat every 1000 frames
+    let mut scheduler = ClickScheduler::new(60.0, Some(4), 1, 1000);
+    let first: Vec<_> = (0..2).map(|_| scheduler.next_click().frame).collect();
+    assert_eq!(first, vec![0, 1000]);
+
+    // Immediate change: the next click keeps its place, spacing halves after it
+    scheduler.change_bpm(120.0, ChangeAt::Immediately);
+    let frames: Vec<_> = (0..3).map(|_| scheduler.next_click().frame).collect();
+    assert_eq!(frames, vec![2000, 2500, 3000]);
+
+    // Bar change: waits for beat 8 (the next downbeat), beat count keeps running
+    scheduler.change_bpm(60.0, ChangeAt::NextBar);
+    let clicks: Vec<_> = (0..5).map(|_| scheduler.next_click()).collect();
+    let frames: Vec<_> = clicks.iter().map(|click| click.frame).collect();
+    assert_eq!(frames, vec![3500, 4000, 4500, 5000, 6000]);
+    assert_eq!(clicks[3].beat, 8);
+    assert_eq!(clicks[3].kind, ClickKind::Accent);
+    assert!((scheduler.bpm() - 60.0).abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_click_scheduler_next_beat_change_skips_subdivisions() {
+    use crate::{ChangeAt, ClickScheduler};
+
+    // 60 BPM with eighth notes at 1kHz: one click every 500 frames
+    let mut scheduler = ClickScheduler::new(60.0, None, 2, 1000);
+    scheduler.next_click();
+    scheduler.change_bpm(120.0, ChangeAt::NextBeat);
+
+    let frames: Vec<_> = (0..4).map(|_| scheduler.next_click().frame).collect();
+    assert_eq!(frames, vec![500, 1000, 1250, 1500]);
+}
+
+#[test]
+fn test_click_scheduler_linear_ramp_over_beats() {
+    use crate::{ChangeAt, ClickScheduler, RampLength, TempoRamp};
+
+    // 60 -> 120 BPM over 4 beats at 1kHz: 60, 75, 90, 105 BPM, then 120
+    let mut scheduler = ClickScheduler::new(60.0, Some(4), 1, 1000);
+    scheduler.start_ramp(
+        TempoRamp::new(60.0, 120.0, RampLength::Beats(4)),
+        ChangeAt::Immediately,
+    );
+
+    let frames: Vec<_> = (0..7).map(|_| scheduler.next_click().frame).collect();
+    assert_eq!(frames, vec![0, 1000, 1800, 2467, 3038, 3538, 4038]);
+    assert!(!scheduler.is_ramping());
+    assert!((scheduler.bpm() - 120.0).abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_click_scheduler_ramp_curves_and_lengths() {
+    use crate::{ChangeAt, ClickScheduler, RampCurve, RampLength, TempoRamp};
+
+    // Exponential ramp over 2 bars of 2 beats: each beat multiplies the tempo by the same ratio
+    let ramp = TempoRamp::new(60.0, 240.0, RampLength::Bars(2)).with_curve(RampCurve::Exponential);
+    let mut scheduler = ClickScheduler::new(60.0, Some(2), 1, 1000);
+    scheduler.start_ramp(ramp, ChangeAt::Immediately);
+    let frames: Vec<_> = (0..6).map(|_| scheduler.next_click().frame).collect();
+    let intervals: Vec<_> = frames.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    // 60, 84.85, 120, 169.7, 240 BPM
+    assert_eq!(intervals, vec![1000, 707, 500, 354, 250]);
+
+    // A ramp over seconds is measured on the audio clock; ritardando slows the clicks down
+    let mut scheduler = ClickScheduler::new(120.0, None, 1, 1000);
+    scheduler.start_ramp(
+        TempoRamp::new(120.0, 60.0, RampLength::Seconds(2.0)),
+        ChangeAt::Immediately,
+    );
+    let frames: Vec<_> = (0..6).map(|_| scheduler.next_click().frame).collect();
+    let intervals: Vec<_> = frames.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    assert!(intervals.windows(2).all(|pair| pair[1] >= pair[0]));
+    assert_eq!(*intervals.last().unwrap(), 1000);
+    assert!(!scheduler.is_ramping());
+}
+
+#[test]
+fn test_compound_time_signatures() {
+    use crate::{BeatUnit, ClickKind, TimeSignature};
+
+    let three_four = TimeSignature::new(3, 4);
+    let six_eight = TimeSignature::new(6, 8);
+    assert!(!three_four.is_compound());
+    assert!(six_eight.is_compound());
+    assert_eq!(TimeSignature::new(12, 8).beats_per_measure(), 4);
+    assert_eq!(TimeSignature::new(3, 8).beats_per_measure(), 3);
+    assert_eq!(six_eight.to_string(), "6/8");
+
+    // 6/8 at dotted quarter = 60 clicks on the two pulses of each bar
+    let mut metronome = Metronome::offline(60.0, None, AccentConfig::default()).unwrap();
+    metronome.set_time_signature(Some(six_eight));
+    let clicks: Vec<_> = metronome.click_scheduler(48_000).take(3).collect();
+    assert_eq!(
+        clicks.iter().map(|click| click.frame).collect::<Vec<_>>(),
+        vec![0, 48_000, 96_000]
+    );
+    assert_eq!(clicks[2].kind, ClickKind::Accent);
+
+    // Clicking every eighth groups them in threes, here at eighth = 180 (the same tempo)
+    metronome.set_time_signature(Some(six_eight.with_every_note()));
+    metronome.set_bpm(180.0).unwrap();
+    metronome.set_beat_unit(Some(BeatUnit::EIGHTH));
+    let kinds: Vec<_> = metronome
+        .click_scheduler(48_000)
+        .take(7)
+        .map(|click| (click.frame, click.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (0, ClickKind::Accent),
+            (16_000, ClickKind::Subdivision),
+            (32_000, ClickKind::Subdivision),
+            (48_000, ClickKind::Regular),
+            (64_000, ClickKind::Subdivision),
+            (80_000, ClickKind::Subdivision),
+            (96_000, ClickKind::Accent),
+        ]
+    );
+
+    // 3/4 at quarter = 60 has three equal beats per bar
+    metronome.set_time_signature(Some(three_four));
+    metronome.set_bpm(60.0).unwrap();
+    metronome.set_beat_unit(None);
+    let clicks: Vec<_> = metronome.click_scheduler(48_000).take(4).collect();
+    assert_eq!(clicks[3].frame, 144_000);
+    assert_eq!(clicks[3].kind, ClickKind::Accent);
+
+    metronome.set_time_signature(Some(TimeSignature::new(5, 3)));
+    assert!(metronome.validate().is_err());
+}
+
+#[test]
+fn test_additive_meter_groupings() {
+    use crate::{ClickKind, Grouping, TimeSignature, click_onset_frame};
+
+    let grouping = Grouping::new(&[2, 2, 3]);
+    assert_eq!(grouping.notes(), 7);
+    assert_eq!(grouping.groups(), vec![2, 2, 3]);
+    assert!(grouping.is_group_start(4));
+    assert!(!grouping.is_group_start(5));
+
+    // 7/8 grouped 2+2+3 at eighth = 210 clicks on the uneven pulse
+    let seven_eight = TimeSignature::new(7, 8).with_grouping(&[2, 2, 3]);
+    let mut metronome = Metronome::offline(210.0, None, AccentConfig::default()).unwrap();
+    metronome.set_time_signature(Some(seven_eight));
+    let clicks: Vec<_> = metronome
+        .click_scheduler(48_000)
+        .take(5)
+        .map(|click| (click.beat, click.kind))
+        .collect();
+    assert_eq!(
+        clicks,
+        vec![
+            (0, ClickKind::Accent),
+            (2, ClickKind::SecondaryAccent),
+            (4, ClickKind::SecondaryAccent),
+            (7, ClickKind::Accent),
+            (9, ClickKind::SecondaryAccent),
+        ]
+    );
+    let mut scheduler = metronome.click_scheduler(48_000);
+    scheduler.next_click();
+    assert_eq!(
+        scheduler.peek_frame(),
+        click_onset_frame(2, 210.0, 1, 48_000)
+    );
+    assert_eq!(
+        scheduler.nth(2).unwrap().frame,
+        click_onset_frame(7, 210.0, 1, 48_000)
+    );
+
+    // Clicking every eighth plays the notes inside the groups as subdivisions
+    metronome.set_time_signature(Some(seven_eight.with_every_note()));
+    let kinds: Vec<_> = metronome
+        .click_scheduler(48_000)
+        .take(8)
+        .map(|click| click.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ClickKind::Accent,
+            ClickKind::Subdivision,
+            ClickKind::SecondaryAccent,
+            ClickKind::Subdivision,
+            ClickKind::SecondaryAccent,
+            ClickKind::Subdivision,
+            ClickKind::Subdivision,
+            ClickKind::Accent,
+        ]
+    );
+
+    // The grouping has to cover the whole measure
+    metronome.set_time_signature(Some(TimeSignature::new(7, 8).with_grouping(&[3, 3])));
+    assert!(metronome.validate().is_err());
+}
+
+#[test]
+fn test_beat_accent_levels() {
+    use crate::{
+        AccentLevel, ClickKind, ClickScheduler, RenderLength, Voice, WaveType, render_samples,
+    };
+
+    let config = AccentConfig::default().set_beat_levels(&[
+        AccentLevel::Weak,
+        AccentLevel::Strong,
+        AccentLevel::Mute,
+        AccentLevel::Ghost,
+    ]);
+    let voices: Vec<_> = ClickScheduler::new(120.0, Some(4), 1, 48_000)
+        .take(5)
+        .map(|click| config.voice_for_click(&click, Some(4)))
+        .collect();
+    let default = AccentConfig::default();
+    assert_eq!(voices[0], Some(default.voice_for(ClickKind::Regular)));
+    assert_eq!(voices[1], Some(default.voice_for(ClickKind::Accent)));
+    assert_eq!(voices[2], None);
+    assert!((voices[3].as_ref().unwrap().volume - 0.3).abs() < f32::EPSILON);
+    assert_eq!(voices[4], voices[0]);
+
+    // Each level can have its own sound, which also plays the matching click kind
+    let woodblock = Voice::new(1200.0, 30, WaveType::Triangle, 0.8);
+    let config = config.set_level_sound(AccentLevel::Medium, woodblock.clone());
+    assert_eq!(
+        config.level_voice(AccentLevel::Medium),
+        Some(woodblock.clone())
+    );
+    assert_eq!(config.voice_for(ClickKind::SecondaryAccent), woodblock);
+    assert_eq!(config.level_voice(AccentLevel::Mute), None);
+
+    // A muted beat renders as silence
+    let config = AccentConfig::default().set_beat_levels(&[
+        AccentLevel::Strong,
+        AccentLevel::Weak,
+        AccentLevel::Mute,
+        AccentLevel::Weak,
+    ]);
+    let metronome = Metronome::offline(60.0, Some(4), config).unwrap();
+    let samples = render_samples(&metronome, RenderLength::Bars(1), 1000, 1).unwrap();
+    assert!(samples[1000..1100].iter().any(|&sample| sample != 0.0));
+    assert!(samples[2000..3000].iter().all(|&sample| sample == 0.0));
+}
+
+#[test]
+fn test_polyrhythm_streams_stay_phase_locked() {
+    use crate::{ChangeAt, Polyrhythm, RampLength, TempoRamp};
+
+    let polyrhythm = Polyrhythm::new(&[4, 3]);
+    assert_eq!(polyrhythm.ticks_per_cycle(), 12);
+    assert_eq!(Polyrhythm::new(&[5, 7]).ticks_per_cycle(), 35);
+
+    // 3 against 4 at 60 BPM: a 4 second cycle on a grid of 12 positions
+    let mut metronome = Metronome::offline(60.0, Some(3), AccentConfig::default()).unwrap();
+    metronome.set_polyrhythm(Some(polyrhythm.clone()));
+    let mut scheduler = metronome.click_scheduler(1200);
+    let mut four = Vec::new();
+    let mut three = Vec::new();
+    for click in scheduler.by_ref().take(12) {
+        for voice in metronome.click_voices(&click) {
+            if voice.wave_type == polyrhythm.streams()[0].sound.regular_wave_type {
+                four.push(click.frame);
+            } else {
+                three.push(click.frame);
+            }
+        }
+    }
+    assert_eq!(four, vec![0, 1200, 2400, 3600]);
+    assert_eq!(three, vec![0, 1600, 3200]);
+
+    // Through a tempo ramp and a long session, both streams still share every downbeat
+    metronome
+        .ramp_tempo(
+            TempoRamp::new(60.0, 97.0, RampLength::Bars(3)),
+            ChangeAt::NextBar,
+        )
+        .unwrap();
+    let mut scheduler = metronome.click_scheduler(48_000);
+    for click in scheduler.by_ref().take(12 * 1000) {
+        let voices = metronome.click_voices(&click).len();
+        let tick = click.beat % 4 * 3 + u64::from(click.subdivision);
+        let expected = match tick {
+            0 => 2,
+            4 | 8 => 1,
+            _ if tick % 3 == 0 => 1,
+            _ => 0,
+        };
+        assert_eq!(voices, expected);
+    }
+
+    metronome.set_polyrhythm(Some(Polyrhythm::new(&[4, 0])));
+    assert!(metronome.validate().is_err());
+}
+
+#[test]
+fn test_render_to_wav_without_audio_device() {
+    use crate::{BitDepth, Metronome, RenderLength, render_to_wav};
+
+    // 2 bars of 4/4 at 120 BPM and 8kHz: 8 beats of 4000 frames
+    let metronome = Metronome::offline(120.0, Some(4), AccentConfig::default()).unwrap();
+    let mut wav = Vec::new();
+    render_to_wav(
+        &metronome,
+        RenderLength::Bars(2),
+        8000,
+        BitDepth::Int16,
+        &mut wav,
+    )
+    .expect("Failed to render metronome");
+
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[8..12], b"WAVE");
+    assert_eq!(u16::from_le_bytes([wav[22], wav[23]]), 1); // mono
+    assert_eq!(
+        u32::from_le_bytes([wav[24], wav[25], wav[26], wav[27]]),
+        8000
+    );
+    assert_eq!(&wav[36..40], b"data");
+    let data_len = u32::from_le_bytes([wav[40], wav[41], wav[42], wav[43]]) as usize;
+    assert_eq!(data_len, 8 * 4000 * 2);
+    assert_eq!(wav.len(), 44 + data_len);
+
+    let samples: Vec<i16> = wav[44..]
+        .chunks_exact(2)
+        .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]))
+        .collect();
+
+    // Each beat starts with a click and is silent once the 100-150ms click has ended
+    for beat in 0..8 {
+        let start = beat * 4000;
+        assert!(samples[start..start + 800].iter().any(|&s| s != 0));
+        assert!(samples[start + 1200..start + 4000].iter().all(|&s| s == 0));
+    }
+
+    // The accent (150ms) is longer than regular beats (100ms)
+    assert!(samples[1000..1100].iter().any(|&s| s != 0));
+    assert!(samples[4000 + 800..4000 + 900].iter().all(|&s| s == 0));
+}
+
+#[test]
+fn test_render_bit_depths() {
+    use crate::{BitDepth, Metronome, RenderLength, render_to_wav};
+
+    let metronome = Metronome::offline(60.0, None, AccentConfig::default()).unwrap();
+    for (bit_depth, bytes_per_sample, format_tag) in [
+        (BitDepth::Int16, 2, 1u16),
+        (BitDepth::Int24, 3, 1),
+        (BitDepth::Float32, 4, 3),
+    ] {
+        let mut wav = Vec::new();
+        render_to_wav(
+            &metronome,
+            RenderLength::Milliseconds(500),
+            44_100,
+            bit_depth,
+            &mut wav,
+        )
+        .expect("Failed to render metronome");
+
+        assert_eq!(u16::from_le_bytes([wav[20], wav[21]]), format_tag);
+        assert_eq!(u16::from_le_bytes([wav[34], wav[35]]), bit_depth.bits());
+        let riff_len = u32::from_le_bytes([wav[4], wav[5], wav[6], wav[7]]) as usize;
+        assert_eq!(riff_len + 8, wav.len());
+        assert!(wav.len() > 22_050 * bytes_per_sample);
+    }
+
+    // An odd-length data chunk gets a pad byte that its length doesn't count
+    let mut wav = Vec::new();
+    render_to_wav(
+        &metronome,
+        RenderLength::Milliseconds(3),
+        1000,
+        BitDepth::Int24,
+        &mut wav,
+    )
+    .unwrap();
+    assert_eq!(u32::from_le_bytes([wav[40], wav[41], wav[42], wav[43]]), 9);
+    assert_eq!(wav.len(), 44 + 9 + 1);
+    assert_eq!(
+        u32::from_le_bytes([wav[4], wav[5], wav[6], wav[7]]) as usize + 8,
+        wav.len()
+    );
+
+    // Lengths and rates that don't fit the header are errors rather than overflows
+    let mut wav = Vec::new();
+    let result = render_to_wav(
+        &metronome,
+        RenderLength::Milliseconds(1),
+        u32::MAX,
+        BitDepth::Float32,
+        &mut wav,
+    );
+    assert!(matches!(
+        result,
+        Err(crate::MetronomeError::InvalidConfig(_))
+    ));
+    assert!(wav.is_empty());
+}
+
+#[test]
+fn test_render_samples_interleaved() {
+    use crate::{ClickSamples, Metronome, RenderLength, render_iter, render_samples};
+
+    // 1 bar of 3/4 at 60 BPM and 1kHz in stereo: 3000 frames, 6000 samples
+    let metronome = Metronome::offline(60.0, Some(3), AccentConfig::default()).unwrap();
+    let samples = render_samples(&metronome, RenderLength::Bars(1), 1000, 2).unwrap();
+    assert_eq!(samples.len(), 6000);
+
+    // Both channels of every frame carry the same value
+    assert!(
+        samples
+            .chunks_exact(2)
+            .all(|frame| frame[0].to_bits() == frame[1].to_bits())
+    );
+
+    // Clicks start on each beat: accent 150ms, regular beats 100ms
+    let left: Vec<f32> = samples.iter().step_by(2).copied().collect();
+    assert!(left[1..150].iter().any(|&s| s != 0.0));
+    assert!(left[150..1000].iter().all(|&s| s == 0.0));
+    assert!(left[1001..1100].iter().any(|&s| s != 0.0));
+    assert!(left[1100..2000].iter().all(|&s| s == 0.0));
+
+    // The lazy iterator yields exactly the same samples
+    let lazy: Vec<f32> = render_iter(&metronome, RenderLength::Bars(1), 1000, 2)
+        .unwrap()
+        .collect();
+    assert_eq!(lazy, samples);
+
+    // An endless iterator keeps going past the bar and can be filled block by block
+    let mut endless = ClickSamples::new(&metronome, 1000, 2).unwrap();
+    let mut buffer = vec![0.0; 7];
+    assert_eq!(endless.fill(&mut buffer), 7);
+    assert_eq!(&buffer[..], &samples[..7]);
+    assert_eq!(endless.next(), Some(samples[7]));
+    let mut buffer = vec![0.0; 8000];
+    assert_eq!(endless.fill(&mut buffer), 8000);
+    assert_eq!(&buffer[..5992], &samples[8..]);
+    assert_eq!(endless.size_hint(), (usize::MAX, None));
+
+    // Unplayable settings are rejected before anything is rendered
+    let stalled = Metronome::offline(120.0, Some(0), AccentConfig::default()).unwrap();
+    assert!(render_samples(&stalled, RenderLength::Bars(1), 48_000, 2).is_err());
+    assert!(render_iter(&stalled, RenderLength::Bars(1), 48_000, 2).is_err());
+    assert!(ClickSamples::new(&stalled, 48_000, 2).is_err());
+}
+
+#[test]
+fn test_polymeter_cycles_realign() {
+    use crate::{Polymeter, WaveType};
+
+    // A 4-beat and a 3-beat accent cycle over the same quarter-note pulse
+    let polymeter = Polymeter::new(&[4, 3]);
+    assert_eq!(polymeter.beats_to_realign(), 12);
+
+    let config = AccentConfig::default();
+    let mut metronome = Metronome::offline(120.0, Some(7), config.clone()).unwrap();
+    metronome.set_polymeter(Some(polymeter.clone()));
+    assert_eq!(metronome.beats_per_measure(), Some(4));
+
+    let four = &polymeter.cycles()[0].accent;
+    let three = &polymeter.cycles()[1].accent;
+    assert_eq!(three.wave_type, WaveType::Triangle);
+
+    let mut scheduler = metronome.click_scheduler(48_000);
+    for click in scheduler.by_ref().take(25) {
+        let voices = metronome.click_voices(&click);
+        let expected: Vec<_> = match (click.beat % 4 == 0, click.beat % 3 == 0) {
+            (true, true) => vec![four.clone(), three.clone()],
+            (true, false) => vec![four.clone()],
+            (false, true) => vec![three.clone()],
+            (false, false) => vec![config.voice_for(crate::ClickKind::Regular)],
+        };
+        assert_eq!(voices, expected, "beat {}", click.beat);
+        assert_eq!(click.frame, click.beat * 24_000);
+    }
+
+    // Polymeters and polyrhythms replace each other
+    metronome.set_polyrhythm(Some(crate::Polyrhythm::new(&[3, 2])));
+    assert!(metronome.polymeter().is_none());
+    metronome.set_polymeter(Some(Polymeter::new(&[5, 0])));
+    assert!(metronome.polyrhythm().is_none());
+    assert!(metronome.validate().is_err());
+}
+
+#[test]
+fn test_mixer_layers_metronomes() {
+    use crate::voice::{Voice, VoiceMixer};
+    use crate::{MetronomeMixer, WaveType};
+
+    let sink = Arc::new(CapturingSink::new(48_000, 2));
+    let mixer = MetronomeMixer::with_sink(sink.clone());
+    let fast = Metronome::offline(240.0, None, AccentConfig::default()).unwrap();
+    let slow = Metronome::offline(120.0, None, AccentConfig::default()).unwrap();
+    let fast_channel = mixer.add(&fast, 1.0);
+    let slow_channel = mixer.add(&slow, 0.25);
+
+    // Starting a metronome the usual way doesn't stop the mixer's channels, and starting
+    // a playing channel again does nothing
+    let source_events = fast.events();
+    let channel_events = fast_channel.metronome().events();
+    let solo = Metronome::with_sink(60.0, None, Arc::new(NullSink::default())).unwrap();
+    mixer.start_all().unwrap();
+    fast_channel.start().unwrap();
+    solo.start().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(700));
+    assert!(fast_channel.is_playing() && slow_channel.is_playing());
+    mixer.stop_all();
+    solo.stop();
+    assert!(!fast_channel.is_playing() && !slow_channel.is_playing());
+
+    // A channel's events go to its own listeners only, from a single thread
+    let beats: Vec<u64> = channel_events.iter().map(|event| event.beat).collect();
+    assert!(beats.len() >= 2);
+    assert!(beats.iter().copied().eq(0..beats.len() as u64));
+    assert!(source_events.try_recv().is_err());
+
+    // Both channels start on the same frame, each with its own gain
+    let voices = sink.voices();
+    let loud: Vec<_> = voices.iter().filter(|voice| voice.volume > 0.5).collect();
+    let quiet: Vec<_> = voices.iter().filter(|voice| voice.volume <= 0.5).collect();
+    assert!(loud.len() >= 2 && !quiet.is_empty());
+    assert_eq!(loud[0].start_frame, quiet[0].start_frame);
+    assert!(quiet.iter().all(|voice| voice.volume <= 0.25));
+
+    // Tempo changes through the original metronome reach its channel
+    fast.set_bpm(200.0).unwrap();
+    assert!((fast_channel.metronome().bpm() - 200.0).abs() < f64::EPSILON);
+
+    mixer.remove(&slow_channel);
+    assert_eq!(mixer.channels().len(), 1);
+
+    // Overlapping clicks are turned down rather than clipped, which would flatten two thirds of the wave
+    let mut voice_mixer = VoiceMixer::new(48_000);
+    for _ in 0..2 {
+        voice_mixer.add(Voice::new(1000.0, 100, WaveType::Sine, 1.0).at_frame(0), 0);
+    }
+    let mut buffer = vec![0.0; 4800];
+    voice_mixer.render(&mut buffer, 0);
+    let clipped = buffer.iter().filter(|s| s.abs() >= 0.999).count();
+    assert!(buffer.iter().all(|s| s.abs() <= 1.0));
+    assert!(clipped < buffer.len() / 10, "{clipped} clipped samples");
+}
+
+#[test]
+fn test_swing_delays_offbeat_subdivisions() {
+    use crate::Swing;
+
+    // Triplet swing on eighths at 120 BPM: beats every 24000 frames, offbeats 2/3 of the way
+    let mut metronome =
+        Metronome::offline(120.0, Some(4), AccentConfig::default().set_subdivisions(2)).unwrap();
+    metronome.set_swing(Swing::TRIPLET);
+    let scheduler = metronome.click_scheduler(48_000);
+    let frames: Vec<u64> = scheduler.take(6).map(|click| click.frame).collect();
+    assert_eq!(frames, vec![0, 16_000, 24_000, 40_000, 48_000, 64_000]);
+
+    // Hard swing on sixteenths swings each pair within the beat
+    let mut metronome =
+        Metronome::offline(120.0, Some(4), AccentConfig::default().set_subdivisions(4)).unwrap();
+    metronome.set_swing(Swing::percent(75.0));
+    let mut scheduler = metronome.click_scheduler(48_000);
+    assert_eq!(scheduler.peek_frame(), 0);
+    scheduler.next_click();
+    assert_eq!(scheduler.peek_frame(), 9_000);
+    let frames: Vec<u64> = scheduler.take(4).map(|click| click.frame).collect();
+    assert_eq!(frames, vec![9_000, 12_000, 21_000, 24_000]);
+
+    // Triplets have no pairs to swing
+    let mut metronome =
+        Metronome::offline(120.0, None, AccentConfig::default().set_subdivisions(3)).unwrap();
+    metronome.set_swing(Swing::HARD);
+    let frames: Vec<u64> = metronome
+        .click_scheduler(48_000)
+        .take(3)
+        .map(|click| click.frame)
+        .collect();
+    assert_eq!(frames, vec![0, 8_000, 16_000]);
+
+    assert!(Swing::STRAIGHT.is_straight());
+    metronome.set_swing(Swing::percent(40.0));
+    assert!(metronome.validate().is_err());
+}
+
+#[test]
+fn test_beat_callbacks_describe_every_click() {
+    use crate::{AccentLevel, AudioSink, BeatEvent, ClickKind};
+    use std::sync::Mutex;
+
+    // 600 BPM in 3/4 with eighth notes: a click every 50ms
+    let sink = Arc::new(CapturingSink::new(48_000, 2));
+    let mut metronome = Metronome::with_sink(600.0, Some(3), sink.clone()).unwrap();
+    metronome.set_accent_config(AccentConfig::default().set_subdivisions(2));
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let received = Arc::clone(&events);
+    let clock = Arc::clone(&sink);
+    metronome.on_beat(move |event: &BeatEvent| {
+        received
+            .lock()
+            .unwrap()
+            .push((event.clone(), clock.frames_played()));
+    });
+    let removed = metronome.on_beat(|_| panic!("removed callbacks must not be called"));
+    metronome.remove_callback(removed);
+
+    metronome.start().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(800));
+    metronome.stop();
+
+    let events = events.lock().unwrap().clone();
+    assert!(events.len() >= 8, "only {} events", events.len());
+    let frames: Vec<u64> = sink
+        .voices()
+        .iter()
+        .filter_map(|voice| voice.start_frame)
+        .collect();
+    for (index, (event, fired_at)) in events.iter().enumerate() {
+        // Events arrive in order, once their click is due, matching what was played
+        assert!(*fired_at >= event.frame);
+        assert!(frames.contains(&event.frame));
+        let index = u64::try_from(index).unwrap();
+        assert_eq!(event.beat, index / 2);
+        assert_eq!(u64::from(event.subdivision), index % 2);
+        assert_eq!(event.bar, event.beat / 3);
+        assert_eq!(u64::from(event.beat_in_bar), event.beat % 3);
+
+        let expected = match (event.subdivision, event.beat_in_bar) {
+            (0, 0) => Some(AccentLevel::Strong),
+            (0, _) => Some(AccentLevel::Weak),
+            _ => None,
+        };
+        assert_eq!(event.level, expected);
+        assert_eq!(event.kind == ClickKind::Subdivision, event.level.is_none());
+    }
+
+    // 3 against 2 fires only on the 4 of every 6 grid positions where a stream plays
+    let sink = Arc::new(CapturingSink::new(48_000, 2));
+    let mut metronome = Metronome::with_sink(300.0, Some(3), sink.clone()).unwrap();
+    metronome.set_polyrhythm(Some(crate::Polyrhythm::new(&[3, 2])));
+    let events = metronome.start_with_events().unwrap();
+    let received: Vec<BeatEvent> = events.iter().take(12).collect();
+    metronome.stop();
+    let frames: Vec<u64> = sink
+        .voices()
+        .iter()
+        .filter_map(|voice| voice.start_frame)
+        .collect();
+    let ticks: Vec<_> = received
+        .iter()
+        .map(|event| {
+            (
+                event.beat % 3 * 2 + u64::from(event.subdivision),
+                event.muted,
+            )
+        })
+        .collect();
+    assert_eq!(ticks[..4], [(0, false), (2, false), (3, false), (4, false)]);
+    assert_eq!(ticks[..4], ticks[4..8]);
+    assert!(received.iter().all(|event| frames.contains(&event.frame)));
+}
+
+#[test]
+fn test_restarting_only_closes_channels_of_the_old_run() {
+    use std::time::Duration;
+
+    let metronome = Metronome::with_sink(480.0, Some(4), Arc::new(NullSink::default())).unwrap();
+    let old = metronome.start_with_events().unwrap();
+    old.recv_timeout(Duration::from_secs(1)).unwrap();
+
+    // The old thread exits after the restart without closing the new channel, and a
+    // single thread plays the new run
+    metronome.stop();
+    let events = metronome.start_with_events().unwrap();
+    assert!(old.iter().count() < 4);
+    let beats: Vec<u64> = events.iter().take(6).map(|event| event.beat).collect();
+    assert_eq!(beats, [0, 1, 2, 3, 4, 5]);
+
+    // Starting again while playing ends the previous run's channels only
+    let restarted = metronome.start_with_events().unwrap();
+    assert!(events.iter().all(|event| event.beat >= 6));
+    assert_eq!(
+        restarted.recv_timeout(Duration::from_secs(1)).unwrap().beat,
+        0
+    );
+    metronome.stop();
+    assert!(restarted.iter().count() < 4);
+    assert!(!metronome.is_playing());
+}
+
+#[test]
+fn test_beat_event_channel_carries_audible_time() {
+    use crate::{AudioSink, voice::Voice};
+    use std::time::{Duration, Instant};
+
+    /// A sink whose clicks reach the speakers 20ms after they are played
+    struct LatentSink(NullSink);
+
+    impl AudioSink for LatentSink {
+        fn sample_rate(&self) -> u32 {
+            self.0.sample_rate()
+        }
+
+        fn channels(&self) -> u16 {
+            self.0.channels()
+        }
+
+        fn frames_played(&self) -> u64 {
+            self.0.frames_played()
+        }
+
+        fn play(&self, voice: Voice) {
+            self.0.play(voice);
+        }
+
+        fn output_latency(&self) -> Duration {
+            Duration::from_millis(20)
+        }
+    }
+
+    let metronome =
+        Metronome::with_sink(480.0, Some(4), Arc::new(LatentSink(NullSink::default()))).unwrap();
+    let events = metronome.start_with_events().unwrap();
+
+    // Events arrive before their click is due, in order
+    let first = events.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert!(Instant::now() < first.scheduled_at);
+    assert_eq!(
+        first.audible_at - first.scheduled_at,
+        Duration::from_millis(20)
+    );
+    assert_eq!((first.bar, first.beat_in_bar), (0, 0));
+
+    let later: Vec<_> = events.iter().take(5).collect();
+    for (beat, event) in (1..).zip(&later) {
+        assert_eq!(event.beat, beat);
+        assert!(event.scheduled_at > first.scheduled_at);
+        // 480 BPM is a click every 6000 frames at 48kHz
+        assert_eq!(event.frame - first.frame, beat * 6000);
+    }
+
+    // Dropping the receiver closes the channel without disturbing playback
+    drop(events);
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(metronome.is_playing());
+    metronome.stop();
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_run_for_bars_streams_every_beat() {
+    use crate::{AudioSink, voice::Voice};
+    use futures_util::StreamExt;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// A sink whose clock jumps a quarter of a second every time it is read
+    #[derive(Default)]
+    struct VirtualClockSink(AtomicU64);
+
+    impl AudioSink for VirtualClockSink {
+        fn sample_rate(&self) -> u32 {
+            48_000
+        }
+
+        fn channels(&self) -> u16 {
+            2
+        }
+
+        fn frames_played(&self) -> u64 {
+            self.0.fetch_add(12_000, Ordering::Relaxed)
+        }
+
+        fn play(&self, _voice: Voice) {}
+    }
+
+    // 240 BPM in 4/4: a beat every 12000 frames
+    let metronome =
+        Metronome::with_sink(240.0, Some(4), Arc::new(VirtualClockSink::default())).unwrap();
+    let beats = metronome.beat_stream();
+    metronome.run_for_bars(2).await.unwrap();
+    assert!(!metronome.is_playing());
+
+    // The stream ends with the run, after exactly two bars
+    let events: Vec<_> = beats.collect().await;
+    assert_eq!(events.len(), 8);
+    for (beat, event) in (0..).zip(&events) {
+        assert_eq!(event.beat, beat);
+        assert_eq!(
+            (event.bar, u64::from(event.beat_in_bar)),
+            (beat / 4, beat % 4)
+        );
+        assert_eq!(event.frame - events[0].frame, beat * 12_000);
+    }
+
+    // Stopping the metronome from elsewhere fails the run
+    let metronome = Metronome::with_sink(240.0, Some(4), Arc::new(NullSink::default())).unwrap();
+    let handle = metronome.clone();
+    let stopper = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        handle.stop();
+    });
+    assert!(matches!(
+        metronome.run_for_bars(100).await,
+        Err(crate::MetronomeError::Stopped)
+    ));
+    stopper.join().unwrap();
+
+    // Dropping the future stops its run, but not a run started after it
+    let mut run = Box::pin(metronome.run_for_bars(100));
+    assert!(futures_util::poll!(&mut run).is_pending());
+    assert!(metronome.is_playing());
+    drop(run);
+    assert!(!metronome.is_playing());
+
+    let mut run = Box::pin(metronome.run_for_bars(100));
+    assert!(futures_util::poll!(&mut run).is_pending());
+    metronome.start().unwrap();
+    drop(run);
+    assert!(metronome.is_playing());
+    metronome.stop();
+}
+
+#[test]
+fn test_tap_tempo_rejects_outliers_and_resets() {
+    use crate::TapTempo;
+    use std::time::Instant;
+
+    let start = Instant::now();
+    let at = |millis| start + Duration::from_millis(millis);
+    let mut tap = TapTempo::new();
+    assert_eq!(tap.tap_at(at(0)), None);
+
+    // Steady taps at 120 BPM, with one tap 150ms late
+    for millis in [500, 1000, 1500, 2150, 2500, 3000] {
+        tap.tap_at(at(millis));
+    }
+    assert_eq!(tap.tap_count(), 7);
+    let bpm = tap.bpm().unwrap();
+    assert!((bpm - 120.0).abs() < 1e-9, "estimated {bpm} BPM");
+
+    // Only the last intervals of the window count, so the estimate follows a new tempo
+    let mut tap = TapTempo::new().with_window(3);
+    for millis in [0, 500, 1000, 1500, 1900, 2300, 2700] {
+        tap.tap_at(at(millis));
+    }
+    let bpm = tap.bpm().unwrap();
+    assert!((bpm - 150.0).abs() < 1e-9, "estimated {bpm} BPM");
+
+    let metronome = Metronome::offline(60.0, Some(4), AccentConfig::default()).unwrap();
+    tap.apply_to(&metronome).unwrap();
+    assert!((metronome.bpm() - 150.0).abs() < 1e-9);
+
+    // Taps at the same instant or bouncing right after each other don't count
+    let mut bouncy = TapTempo::new();
+    for millis in [0, 0, 40, 500, 500, 510] {
+        bouncy.tap_at(at(millis));
+    }
+    assert_eq!(bouncy.tap_count(), 2);
+    assert!((bouncy.bpm().unwrap() - 120.0).abs() < 1e-9);
+    let mut double = TapTempo::new();
+    double.tap_at(at(0));
+    assert_eq!(double.tap_at(at(0)), None);
+    assert!(double.apply_to(&metronome).is_err());
+    assert!((metronome.bpm() - 150.0).abs() < 1e-9);
+
+    // A pause longer than the timeout starts over
+    assert_eq!(tap.tap_at(at(5000)), None);
+    assert_eq!(tap.tap_count(), 1);
+    assert!(tap.is_tapping());
+    tap.reset();
+    assert_eq!(tap.tap_count(), 0);
+    assert!(!tap.is_tapping());
+
+    // Once the user has stopped tapping, the estimate is gone even without another tap
+    let mut stale = TapTempo::new().with_tolerance(f64::INFINITY);
+    let past = Instant::now().checked_sub(Duration::from_secs(5)).unwrap();
+    assert_eq!(stale.tap_at(past), None);
+    assert_eq!(stale.tap_at(past + Duration::from_millis(500)), Some(120.0));
+    assert_eq!(stale.bpm(), None);
+    assert!(stale.apply_to(&metronome).is_err());
+}
+
+#[test]
+fn test_detect_tempo_of_rendered_click_tracks() {
+    use crate::{
+        BitDepth, RenderLength, detect_tempo, detect_tempo_from_wav, render_samples, render_to_wav,
+    };
+
+    // A WAV click track at an uneven tempo in 3/4
+    let metronome = Metronome::offline(97.0, Some(3), AccentConfig::default()).unwrap();
+    let mut wav = Vec::new();
+    render_to_wav(
+        &metronome,
+        RenderLength::Bars(6),
+        44_100,
+        BitDepth::Int24,
+        &mut wav,
+    )
+    .unwrap();
+    let estimate = detect_tempo_from_wav(wav.as_slice()).unwrap().unwrap();
+    assert!(
+        (estimate.bpm - 97.0).abs() < 0.05,
+        "estimated {} BPM",
+        estimate.bpm
+    );
+    assert_eq!(estimate.beats_per_measure, Some(3));
+    assert!(estimate.first_beat < Duration::from_millis(2));
+    assert_eq!(estimate.first_downbeat, estimate.first_beat);
+
+    // A stereo recording starting half a beat before the third beat of a 4/4 bar
+    let metronome = Metronome::offline(120.0, Some(4), AccentConfig::default()).unwrap();
+    let samples = render_samples(&metronome, RenderLength::Bars(8), 48_000, 2).unwrap();
+    let estimate = detect_tempo(&samples[36_000 * 2..], 48_000, 2).unwrap();
+    assert!(
+        (estimate.bpm - 120.0).abs() < 0.05,
+        "estimated {} BPM",
+        estimate.bpm
+    );
+    assert_eq!(estimate.beats_per_measure, Some(4));
+    let near =
+        |actual: Duration, millis: f64| actual.as_secs_f64().mul_add(1000.0, -millis).abs() < 2.0;
+    assert!(
+        near(estimate.first_beat, 250.0),
+        "{:?}",
+        estimate.first_beat
+    );
+    assert!(
+        near(estimate.first_downbeat, 1250.0),
+        "{:?}",
+        estimate.first_downbeat
+    );
+    assert!(near(
+        estimate.downbeat_after(Duration::from_secs(2)).unwrap(),
+        3250.0
+    ));
+
+    // Estimates without a playable beat give no times instead of panicking
+    for bpm in [0.0, -90.0, f64::NAN, f64::INFINITY] {
+        let broken = crate::TempoEstimate { bpm, ..estimate };
+        assert_eq!(broken.beat_duration(), None);
+        assert_eq!(broken.downbeat_after(Duration::from_secs(2)), None);
+    }
+    let empty_measure = crate::TempoEstimate {
+        beats_per_measure: Some(0),
+        ..estimate
+    };
+    assert_eq!(empty_measure.downbeat_after(Duration::from_secs(2)), None);
+    assert_eq!(estimate.downbeat_after(Duration::MAX), None);
+
+    // Silence, a missing sample rate and unsupported files
+    assert_eq!(detect_tempo(&vec![0.0; 48_000], 48_000, 1), None);
+    assert_eq!(detect_tempo(&samples, 0, 1), None);
+    assert!(detect_tempo_from_wav(&b"RIFF\0\0\0\0WAVEjunk"[..]).is_err());
+}
+
+#[test]
+fn test_count_in_precedes_main_click() {
+    use crate::{CountIn, RenderLength, Voice, WaveType, render_samples};
+
+    // One bar of a distinct voice before one bar of 4/4 at 120 BPM
+    let plain = Metronome::offline(120.0, Some(4), AccentConfig::default()).unwrap();
+    let mut counted = plain.clone();
+    let woodblock = Voice::new(1200.0, 40, WaveType::Square, 0.5);
+    counted.set_count_in(Some(CountIn::bars(1).with_voice(woodblock)));
+
+    let main = render_samples(&plain, RenderLength::Bars(1), 48_000, 1).unwrap();
+    let samples = render_samples(&counted, RenderLength::Bars(1), 48_000, 1).unwrap();
+    assert_eq!(samples.len(), main.len() * 2);
+    let (count_in, rest) = samples.split_at(main.len());
+    assert_eq!(rest, main.as_slice());
+    for beat in 0..4 {
+        let click = &count_in[beat * 24_000..beat * 24_000 + 1920];
+        assert!(click.iter().all(|sample| sample.abs() <= 0.5));
+        assert!(click.iter().any(|sample| sample.abs() > 0.4));
+    }
+
+    // Events mark the count-in, and the main click counts from bar 0 after it
+    let mut metronome =
+        Metronome::with_sink(480.0, Some(4), Arc::new(NullSink::default())).unwrap();
+    metronome.set_count_in(Some(CountIn::bars(1)));
+    let events = metronome.start_with_events().unwrap();
+    let received: Vec<_> = events.iter().take(8).collect();
+    metronome.stop();
+    for (index, event) in (0..).zip(&received) {
+        assert_eq!(event.count_in, index < 4);
+        assert_eq!((event.bar, event.beat), (0, index % 4));
+        assert_eq!(event.frame - received[0].frame, index * 6000);
+    }
+}
+
+#[test]
+fn test_gap_trainer_mutes_bars_but_keeps_events() {
+    use crate::{ClickScheduler, GapTrainer, RenderLength, render_samples};
+    use std::time::Instant;
+
+    // One bar with the click, one without, at 120 BPM in 4/4 (two seconds per bar)
+    let mut metronome = Metronome::offline(120.0, Some(4), AccentConfig::default()).unwrap();
+    metronome.set_gap_trainer(Some(GapTrainer::fixed(1, 1)));
+    let samples = render_samples(&metronome, RenderLength::Bars(4), 48_000, 1).unwrap();
+    for (bar, audio) in samples.chunks(96_000).enumerate() {
+        let silent = audio.iter().all(|sample| *sample == 0.0);
+        assert_eq!(silent, bar % 2 == 1, "bar {bar}");
+    }
+
+    // Muted beats still produce events that know where the player should be
+    let mut scheduler = ClickScheduler::new(120.0, Some(4), 1, 48_000);
+    for beat in 0..8 {
+        let click = scheduler.next_click();
+        let event = metronome.beat_event(&click, Some(4), Instant::now(), Duration::ZERO);
+        assert_eq!(event.beat, beat);
+        assert_eq!(event.muted, beat >= 4);
+    }
+
+    // Seeded random gaps repeat exactly, and the first bar is always played
+    let random = GapTrainer::random(0.5, 7);
+    let muted: Vec<bool> = (0..200).map(|bar| random.is_muted(bar)).collect();
+    assert_eq!(
+        muted,
+        (0..200)
+            .map(|bar| GapTrainer::random(0.5, 7).is_muted(bar))
+            .collect::<Vec<_>>()
+    );
+    assert_ne!(
+        muted,
+        (0..200)
+            .map(|bar| GapTrainer::random(0.5, 8).is_muted(bar))
+            .collect::<Vec<_>>()
+    );
+    assert!(!muted[0]);
+    let count = muted.iter().filter(|muted| **muted).count();
+    assert!((70..130).contains(&count), "{count} of 200 bars muted");
+
+    // Progressive gaps grow by a bar per cycle, then stay at the longest gap
+    let progressive = GapTrainer::progressive(2, 1, 3);
+    let pattern: String = (0..22)
+        .map(|bar| if progressive.is_muted(bar) { '-' } else { 'x' })
+        .collect();
+    assert_eq!(pattern, "xx-xx--xx---xx---xx---");
+    assert!(GapTrainer::fixed(0, 2).validate().is_err());
+}
+
+#[test]
+fn test_speed_trainer_steps_tempo_at_bar_lines() {
+    use crate::{RenderLength, SpeedTrainer, render_samples};
+
+    // 120, 180 and 240 BPM for a bar each, then back to 120, in one unbroken track
+    let mut metronome = Metronome::offline(60.0, Some(4), AccentConfig::default()).unwrap();
+    let trainer = SpeedTrainer::new(120.0, 60.0, 1, 240.0).with_cycles(true);
+    metronome.set_speed_trainer(Some(trainer));
+    let samples = render_samples(&metronome, RenderLength::Bars(4), 48_000, 1).unwrap();
+    assert_eq!(samples.len(), 96_000 + 64_000 + 48_000 + 96_000);
+
+    let mut onset = 0;
+    for beat_frames in [24_000, 16_000, 12_000, 24_000] {
+        for _ in 0..4 {
+            assert!(
+                samples[onset - onset.min(200)..onset]
+                    .iter()
+                    .all(|s| *s == 0.0)
+            );
+            assert!(samples[onset..onset + 100].iter().any(|s| *s != 0.0));
+            onset += beat_frames;
+        }
+    }
+
+    // Without cycling the tempo stays at the top, where the last step may be smaller
+    let trainer = SpeedTrainer::new(60.0, 7.0, 4, 80.0).with_loop(2, 3);
+    let tempos: Vec<f64> = [0, 5, 6, 12, 18, 24, 600]
+        .into_iter()
+        .map(|bar| trainer.bpm_at_bar(bar))
+        .collect();
+    assert_eq!(tempos, [60.0, 60.0, 67.0, 74.0, 80.0, 80.0, 80.0]);
+    assert_eq!(trainer.tempo_count(), 4);
+    assert!(SpeedTrainer::new(100.0, 5.0, 2, 90.0).validate().is_err());
+
+    // A step too small to ever reach the top is rejected, and doesn't panic if played anyway
+    let crawl = SpeedTrainer::new(60.0, 1e-300, 1, 240.0).with_cycles(true);
+    assert!(crawl.validate().is_err());
+    assert!((60.0..=240.0).contains(&crawl.bpm_at_bar(u64::MAX - 1)));
+    metronome.set_speed_trainer(Some(crawl));
+    assert!(render_samples(&metronome, RenderLength::Bars(1), 48_000, 1).is_err());
+}
+
+#[test]
+fn test_jump_drill_picks_seeded_tempos_with_cues() {
+    use crate::{RenderLength, SpeedTrainer, TempoJumpDrill, Voice, WaveType, render_samples};
+
+    // Tempos repeat for the same seed, stay in range and hold for `every_bars` bars
+    let drill = TempoJumpDrill::new(60.0, 180.0, 2, 11);
+    let tempos: Vec<f64> = (0..100).map(|bar| drill.bpm_at_bar(bar)).collect();
+    let again = TempoJumpDrill::new(60.0, 180.0, 2, 11);
+    assert_eq!(
+        tempos,
+        (0..100)
+            .map(|bar| again.bpm_at_bar(bar))
+            .collect::<Vec<_>>()
+    );
+    assert!(
+        tempos
+            .iter()
+            .all(|bpm| (60.0..=180.0).contains(bpm) && bpm.fract() == 0.0)
+    );
+    assert!(
+        tempos
+            .chunks(2)
+            .all(|pair| pair[0].total_cmp(&pair[1]).is_eq())
+    );
+    assert!(
+        tempos
+            .windows(2)
+            .any(|pair| pair[0].total_cmp(&pair[1]).is_ne())
+    );
+
+    // Each bar of a rendered track is as long as its tempo, and jumps start with the cue
+    let cue = Voice::new(2000.0, 40, WaveType::Square, 0.5);
+    let drill = TempoJumpDrill::new(90.0, 150.0, 1, 3).with_cue(cue);
+    let mut metronome = Metronome::offline(120.0, Some(4), AccentConfig::default()).unwrap();
+    metronome.set_jump_drill(Some(drill.clone()));
+    let samples = render_samples(&metronome, RenderLength::Bars(4), 48_000, 1).unwrap();
+    let mut downbeat = 0;
+    for bar in 0..4 {
+        let click = &samples[downbeat..downbeat + 1920];
+        let cued = click.iter().all(|sample| sample.abs() <= 0.5);
+        assert_eq!(cued, drill.is_jump(bar), "bar {bar}");
+        let beat_frames = 60.0 * 48_000.0 / drill.bpm_at_bar(bar);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let bar_frames = (4.0 * beat_frames).round() as usize;
+        downbeat += bar_frames;
+    }
+    assert!(samples.len().abs_diff(downbeat) <= 4);
+
+    // Drills that can't be played are refused by the metronome but don't panic
+    for (min_bpm, max_bpm) in [(150.0, 90.0), (f64::NAN, 120.0), (90.0, f64::NAN)] {
+        let broken = TempoJumpDrill::new(min_bpm, max_bpm, 1, 3);
+        let _ = broken.bpm_at_bar(5);
+        assert!(broken.validate().is_err());
+    }
+
+    // Speed trainers and tempo-jump drills replace each other
+    metronome.set_speed_trainer(Some(SpeedTrainer::new(90.0, 5.0, 1, 120.0)));
+    assert!(metronome.jump_drill().is_none());
+    metronome.set_jump_drill(Some(drill));
+    assert!(metronome.speed_trainer().is_none());
+    assert!(metronome.validate().is_ok());
+}
+
+#[test]
+fn test_subdivision_ladder_changes_clicks_per_beat_at_bar_lines() {
+    use crate::{ChangeAt, ClickScheduler, RenderLength, SubdivisionLadder, render_samples};
+
+    // 60 BPM in 2/4 at 1kHz: the change to triplets waits for the downbeat of beat 2
+    let mut scheduler = ClickScheduler::new(60.0, Some(2), 2, 1000);
+    scheduler.next_click();
+    scheduler.change_subdivisions(3, ChangeAt::NextBar);
+    let clicks: Vec<_> = (0..6).map(|_| scheduler.next_click()).collect();
+    let placed: Vec<_> = clicks
+        .iter()
+        .map(|click| (click.frame, click.beat, click.subdivision))
+        .collect();
+    assert_eq!(
+        placed,
+        [
+            (500, 0, 1),
+            (1000, 1, 0),
+            (1500, 1, 1),
+            (2000, 2, 0),
+            (2333, 2, 1),
+            (2667, 2, 2)
+        ]
+    );
+    assert_eq!(scheduler.subdivisions(), 3);
+
+    // Quarters, eighths, triplets and eighths again at 120 BPM, without a break in the pulse
+    let mut metronome = Metronome::offline(120.0, Some(4), AccentConfig::default()).unwrap();
+    metronome.set_subdivision_ladder(Some(
+        SubdivisionLadder::new(vec![1, 2, 3], 1).with_descent(true),
+    ));
+    let samples = render_samples(&metronome, RenderLength::Bars(4), 48_000, 1).unwrap();
+    assert_eq!(samples.len(), 4 * 96_000);
+    let sounds_at = |frame: usize| samples[frame..frame + 100].iter().any(|s| *s != 0.0);
+    let silent_before = |frame: usize| samples[frame - 200..frame].iter().all(|s| *s == 0.0);
+    for (bar, clicks_per_beat) in [1, 2, 3, 2].into_iter().enumerate() {
+        let spacing = 24_000 / clicks_per_beat;
+        for click in 0..4 * clicks_per_beat {
+            let frame = bar * 96_000 + click * spacing;
+            assert!(sounds_at(frame), "bar {bar} click {click}");
+            assert!(
+                frame == 0 || silent_before(frame),
+                "bar {bar} click {click}"
+            );
+        }
+    }
+    assert!(!sounds_at(12_000));
+    assert!(SubdivisionLadder::new(vec![1, 0], 1).validate().is_err());
+}
@@ -1,13 +1,14 @@
-use cpal::{
-    Device, FromSample, Sample, SizedSample, StreamConfig,
-    traits::{DeviceTrait, StreamTrait},
-};
+use cpal::{Device, FromSample, Sample, SizedSample, StreamConfig, traits::DeviceTrait};
 
 use crate::accent::WaveType;
 use crate::audio::{get_default_host, get_default_output_config, get_default_output_device};
+use crate::engine::AudioEngine;
+use crate::error::MetronomeError;
+use crate::sink::AudioSink;
+use crate::voice::Voice;
 
-/// This function creates an audio stream that generates a continuous sine wave at the specified
-/// frequency and plays it through the given output device for the specified duration.
+/// This function submits a sine wave at the specified frequency to the shared audio engine of the
+/// given output device and blocks for the specified duration while it plays.
 ///
 /// # Arguments
 ///
@@ -22,7 +23,7 @@ use crate::audio::{get_default_host, get_default_output_config, get_default_outp
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` on successful playback, or an error if the audio stream cannot be created or played.
+/// Returns `Ok(())` on successful playback, or an error if the audio engine cannot be opened.
 ///
 /// # Examples
 ///
@@ -36,45 +37,27 @@ use crate::audio::{get_default_host, get_default_output_config, get_default_outp
 ///
 /// play_tone::<f32>(&device, &config.into(), 440.0, 1000).expect("Failed to play tone");
 /// ```
-/// 
+///
 /// # Errors
-/// 
-/// Returns an error if the stream fails to build or play.
+///
+/// Returns an error if the shared audio engine for the device cannot be opened.
 pub fn play_tone<T>(
     device: &Device,
     config: &StreamConfig,
     frequency: f32,
     duration_ms: u64,
-) -> Result<(), Box<dyn std::error::Error>>
+) -> Result<(), MetronomeError>
 where
     T: SizedSample + FromSample<f32>,
 {
-    #[allow(clippy::cast_precision_loss)]
-    let sample_rate = config.sample_rate.0 as f32;
-    let channels = config.channels as usize;
-
-    // Create a sine wave generator for the specified frequency
-    let mut sample_clock = 0f32;
-    let mut next_value = move || {
-        sample_clock = (sample_clock + 1.0) % sample_rate;
-        (sample_clock * frequency * 2.0 * std::f32::consts::PI / sample_rate).sin()
-    };
-
-    let err_fn = |err| eprintln!("an error occurred on stream: {err}");
-
-    let stream = device.build_output_stream(
+    play_tone_with_wave_type_and_volume::<T>(
+        device,
         config,
-        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-            write_data(data, channels, &mut next_value);
-        },
-        err_fn,
-        None,
-    )?;
-    stream.play()?;
-
-    std::thread::sleep(std::time::Duration::from_millis(duration_ms));
-
-    Ok(())
+        frequency,
+        duration_ms,
+        WaveType::Sine,
+        1.0,
+    )
 }
 
 /// Writes audio sample data to the output buffer.
@@ -122,11 +105,11 @@ pub fn create_sine_wave_generator(frequency: f32, sample_rate: f32) -> impl FnMu
 }
 
 /// Plays a beep using the default audio device and configuration.
-/// 
+///
 /// # Errors
-/// 
+///
 /// Returns an error if the default device or configuration cannot be obtained, or if playing the beep fails.
-pub fn play_default_beep() -> Result<(), Box<dyn std::error::Error>> {
+pub fn play_default_beep() -> Result<(), MetronomeError> {
     let host = get_default_host();
     let device = get_default_output_device(&host)?;
     let config = get_default_output_config(&device)?;
@@ -137,14 +120,11 @@ pub fn play_default_beep() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 /// Plays a beep with the specified device and configuration, automatically handling sample format.
-/// 
+///
 /// # Errors
-/// 
+///
 /// Returns an error if the device configuration cannot be obtained or if playing the tone fails.
-pub fn play_beep_with_config(
-    device: &Device,
-    config: &StreamConfig,
-) -> Result<(), Box<dyn std::error::Error>> {
+pub fn play_beep_with_config(device: &Device, config: &StreamConfig) -> Result<(), MetronomeError> {
     match device.default_output_config()?.sample_format() {
         cpal::SampleFormat::F32 => play_tone::<f32>(device, config, 440.0, 1000),
         cpal::SampleFormat::I16 => play_tone::<i16>(device, config, 440.0, 1000),
@@ -152,25 +132,26 @@ pub fn play_beep_with_config(
         sample_format => {
             println!("Unsupported sample format '{sample_format}', trying f32");
             play_tone::<f32>(device, config, 440.0, 1000)
+                .map_err(|_| MetronomeError::UnsupportedSampleFormat(sample_format))
         }
     }
 }
 
 /// Convenience function to play a 440Hz beep for 1 second.
-/// 
+///
 /// # Errors
-/// 
+///
 /// Returns an error if the default device or configuration cannot be obtained, or if playing the beep fails.
-pub fn beep() -> Result<(), Box<dyn std::error::Error>> {
+pub fn beep() -> Result<(), MetronomeError> {
     play_default_beep()
 }
 
 /// Convenience function to play a short beep (250ms) at the specified frequency.
-/// 
+///
 /// # Errors
-/// 
+///
 /// Returns an error if the default device or configuration cannot be obtained, or if playing the beep fails.
-pub fn beep_frequency(frequency: f32) -> Result<(), Box<dyn std::error::Error>> {
+pub fn beep_frequency(frequency: f32) -> Result<(), MetronomeError> {
     let host = get_default_host();
     let device = get_default_output_device(&host)?;
     let config = get_default_output_config(&device)?;
@@ -179,16 +160,16 @@ pub fn beep_frequency(frequency: f32) -> Result<(), Box<dyn std::error::Error>>
 }
 
 /// Plays a beep with full control over frequency and duration.
-/// 
+///
 /// # Errors
-/// 
+///
 /// Returns an error if the device configuration cannot be obtained or if playing the tone fails.
 pub fn play_beep_with_config_and_params(
     device: &Device,
     config: &StreamConfig,
     frequency: f32,
     duration_ms: u64,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), MetronomeError> {
     match device.default_output_config()?.sample_format() {
         cpal::SampleFormat::F32 => play_tone::<f32>(device, config, frequency, duration_ms),
         cpal::SampleFormat::I16 => play_tone::<i16>(device, config, frequency, duration_ms),
@@ -196,80 +177,33 @@ pub fn play_beep_with_config_and_params(
         sample_format => {
             println!("Unsupported sample format '{sample_format}', trying f32");
             play_tone::<f32>(device, config, frequency, duration_ms)
+                .map_err(|_| MetronomeError::UnsupportedSampleFormat(sample_format))
         }
     }
 }
 
 /// Plays a tone with a specified wave type.
-/// 
+///
 /// # Errors
-/// 
-/// Returns an error if the stream fails to build or play.
+///
+/// Returns an error if the shared audio engine for the device cannot be opened.
 pub fn play_tone_with_wave_type<T>(
     device: &Device,
     config: &StreamConfig,
     frequency: f32,
     duration_ms: u64,
     wave_type: WaveType,
-) -> Result<(), Box<dyn std::error::Error>>
+) -> Result<(), MetronomeError>
 where
     T: SizedSample + FromSample<f32>,
 {
-    #[allow(clippy::cast_precision_loss)]
-    let sample_rate = config.sample_rate.0 as f32;
-    let channels = config.channels as usize;
-
-    // Create a wave generator for the specified frequency and wave type
-    let mut sample_clock = 0f32;
-    let mut next_value = move || {
-        sample_clock = (sample_clock + 1.0) % sample_rate;
-        let phase = sample_clock * frequency * 2.0 * std::f32::consts::PI / sample_rate;
-
-        match wave_type {
-            WaveType::Sine => phase.sin(),
-            WaveType::Square => {
-                if phase.sin() >= 0.0 {
-                    1.0
-                } else {
-                    -1.0
-                }
-            }
-            WaveType::Sawtooth => {
-                let normalized_phase = (phase / (2.0 * std::f32::consts::PI)) % 1.0;
-                2.0f32.mul_add(normalized_phase, -1.0)
-            }
-            WaveType::Triangle => {
-                let normalized_phase = (phase / (2.0 * std::f32::consts::PI)) % 1.0;
-                if normalized_phase < 0.5 {
-                    4.0f32.mul_add(normalized_phase, -1.0)
-                } else {
-                    4.0f32.mul_add(-normalized_phase, 3.0)
-                }
-            }
-        }
-    };
-
-    let err_fn = |err| eprintln!("an error occurred on stream: {err}");
-
-    let stream = device.build_output_stream(
-        config,
-        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-            write_data(data, channels, &mut next_value);
-        },
-        err_fn,
-        None,
-    )?;
-    stream.play()?;
-
-    std::thread::sleep(std::time::Duration::from_millis(duration_ms));
-
-    Ok(())
+    play_tone_with_wave_type_and_volume::<T>(device, config, frequency, duration_ms, wave_type, 1.0)
 }
 
 /// Plays a beep with full control over frequency, duration, and wave type.
-/// 
+///
 /// # Errors
-/// 
+///
 /// Returns an error if the device configuration cannot be obtained or if playing the tone fails.
 pub fn play_beep_with_wave_type(
     device: &Device,
@@ -277,7 +211,7 @@ pub fn play_beep_with_wave_type(
     frequency: f32,
     duration_ms: u64,
     wave_type: WaveType,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), MetronomeError> {
     match device.default_output_config()?.sample_format() {
         cpal::SampleFormat::F32 => {
             play_tone_with_wave_type::<f32>(device, config, frequency, duration_ms, wave_type)
@@ -291,14 +225,15 @@ pub fn play_beep_with_wave_type(
         sample_format => {
             println!("Unsupported sample format '{sample_format}', trying f32");
             play_tone_with_wave_type::<f32>(device, config, frequency, duration_ms, wave_type)
+                .map_err(|_| MetronomeError::UnsupportedSampleFormat(sample_format))
         }
     }
 }
 
 /// Plays a beep with full control over frequency, duration, wave type, and volume.
-/// 
+///
 /// # Errors
-/// 
+///
 /// Returns an error if the device configuration cannot be obtained or if playing the tone fails.
 pub fn play_beep_with_wave_type_and_volume(
     device: &Device,
@@ -307,7 +242,7 @@ pub fn play_beep_with_wave_type_and_volume(
     duration_ms: u64,
     wave_type: WaveType,
     volume: f32,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), MetronomeError> {
     match device.default_output_config()?.sample_format() {
         cpal::SampleFormat::F32 => play_tone_with_wave_type_and_volume::<f32>(
             device,
@@ -343,15 +278,16 @@ pub fn play_beep_with_wave_type_and_volume(
                 wave_type,
                 volume,
             )
+            .map_err(|_| MetronomeError::UnsupportedSampleFormat(sample_format))
         }
     }
 }
 
 /// Plays a tone with a specified wave type and volume.
-/// 
+///
 /// # Errors
-/// 
-/// Returns an error if the stream fails to build or play.
+///
+/// Returns an error if the shared audio engine for the device cannot be opened.
 pub fn play_tone_with_wave_type_and_volume<T>(
     device: &Device,
     config: &StreamConfig,
@@ -359,60 +295,64 @@ pub fn play_tone_with_wave_type_and_volume<T>(
     duration_ms: u64,
     wave_type: WaveType,
     volume: f32,
-) -> Result<(), Box<dyn std::error::Error>>
+) -> Result<(), MetronomeError>
 where
     T: SizedSample + FromSample<f32>,
 {
-    #[allow(clippy::cast_precision_loss)]
-    let sample_rate = config.sample_rate.0 as f32;
-    let channels = config.channels as usize;
-    let volume = volume.clamp(0.0, 1.0); // Ensure volume is between 0 and 1
+    let engine = AudioEngine::shared::<T>(device, config)?;
+    play_tone_on_sink(engine.as_ref(), frequency, duration_ms, wave_type, volume);
 
-    // Create a wave generator for the specified frequency and wave type
-    let mut sample_clock = 0f32;
-    let mut next_value = move || {
-        sample_clock = (sample_clock + 1.0) % sample_rate;
-        let phase = sample_clock * frequency * 2.0 * std::f32::consts::PI / sample_rate;
-
-        let base_amplitude = match wave_type {
-            WaveType::Sine => phase.sin(),
-            WaveType::Square => {
-                if phase.sin() >= 0.0 {
-                    1.0
-                } else {
-                    -1.0
-                }
-            }
-            WaveType::Sawtooth => {
-                let normalized_phase = (phase / (2.0 * std::f32::consts::PI)) % 1.0;
-                2.0f32.mul_add(normalized_phase, -1.0)
-            }
-            WaveType::Triangle => {
-                let normalized_phase = (phase / (2.0 * std::f32::consts::PI)) % 1.0;
-                if normalized_phase < 0.5 {
-                    4.0f32.mul_add(normalized_phase, -1.0)
-                } else {
-                    4.0f32.mul_add(-normalized_phase, 3.0)
-                }
-            }
-        };
-
-        base_amplitude * volume // Apply volume scaling
-    };
-
-    let err_fn = |err| eprintln!("an error occurred on stream: {err}");
+    Ok(())
+}
 
-    let stream = device.build_output_stream(
-        config,
-        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-            write_data(data, channels, &mut next_value);
-        },
-        err_fn,
-        None,
-    )?;
-    stream.play()?;
+/// Plays a tone on any audio sink, blocking for its duration.
+///
+/// # Examples
+///
+/// ```
+/// use metronome_rs::{CapturingSink, WaveType, play_tone_on_sink};
+///
+/// let sink = CapturingSink::new(48000, 2);
+/// play_tone_on_sink(&sink, 440.0, 10, WaveType::Sine, 0.5);
+/// assert_eq!(sink.voices().len(), 1);
+/// ```
+pub fn play_tone_on_sink(
+    sink: &dyn AudioSink,
+    frequency: f32,
+    duration_ms: u64,
+    wave_type: WaveType,
+    volume: f32,
+) {
+    sink.play(Voice::new(frequency, duration_ms, wave_type, volume));
 
     std::thread::sleep(std::time::Duration::from_millis(duration_ms));
+}
 
-    Ok(())
+/// Returns the value of the given wave type at `phase` (in radians), in the range -1.0 to 1.0.
+///
+/// This is the waveform used for every tone and metronome click played by this library.
+#[must_use]
+pub fn wave_value(wave_type: &WaveType, phase: f32) -> f32 {
+    match wave_type {
+        WaveType::Sine => phase.sin(),
+        WaveType::Square => {
+            if phase.sin() >= 0.0 {
+                1.0
+            } else {
+                -1.0
+            }
+        }
+        WaveType::Sawtooth => {
+            let normalized_phase = (phase / (2.0 * std::f32::consts::PI)) % 1.0;
+            2.0f32.mul_add(normalized_phase, -1.0)
+        }
+        WaveType::Triangle => {
+            let normalized_phase = (phase / (2.0 * std::f32::consts::PI)) % 1.0;
+            if normalized_phase < 0.5 {
+                4.0f32.mul_add(normalized_phase, -1.0)
+            } else {
+                4.0f32.mul_add(-normalized_phase, 3.0)
+            }
+        }
+    }
 }
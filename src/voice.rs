@@ -0,0 +1,176 @@
+use crate::accent::WaveType;
+
+/// A single sound submitted to an [`AudioEngine`](crate::engine::AudioEngine).
+///
+/// A voice is a fixed-length tone with a frequency, wave type and volume. Voices
+/// are mixed together by the engine, so several clicks may overlap without
+/// opening additional output streams.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Voice {
+    /// Frequency of the tone in Hz
+    pub frequency: f32,
+    /// Duration of the tone in milliseconds
+    pub duration_ms: u64,
+    /// Wave type used to generate the tone
+    pub wave_type: WaveType,
+    /// Volume of the tone (0.0 to 1.0)
+    pub volume: f32,
+    /// Frame on the engine clock at which the voice starts, or `None` to start as soon as possible
+    pub start_frame: Option<u64>,
+}
+
+impl Voice {
+    /// Creates a voice that starts as soon as it reaches the engine.
+    #[must_use]
+    pub const fn new(frequency: f32, duration_ms: u64, wave_type: WaveType, volume: f32) -> Self {
+        Self {
+            frequency,
+            duration_ms,
+            wave_type,
+            volume,
+            start_frame: None,
+        }
+    }
+
+    /// Returns a copy of this voice scheduled to start at the given engine frame.
+    #[must_use]
+    pub const fn at_frame(mut self, frame: u64) -> Self {
+        self.start_frame = Some(frame);
+        self
+    }
+
+    /// Returns the length of this voice in frames at the given sample rate.
+    #[must_use]
+    pub const fn duration_frames(&self, sample_rate: u32) -> u64 {
+        self.duration_ms * sample_rate as u64 / 1000
+    }
+
+    /// Returns the sample value of this voice `offset` frames after it started.
+    #[must_use]
+    pub fn sample_at(&self, offset: u64, sample_rate: u32) -> f32 {
+        // Wrap the clock every second to keep the phase precise for long tones
+        #[allow(clippy::cast_precision_loss)]
+        let sample_clock = (offset % u64::from(sample_rate)) as f32;
+        #[allow(clippy::cast_precision_loss)]
+        let phase = sample_clock * self.frequency * 2.0 * std::f32::consts::PI / sample_rate as f32;
+
+        crate::tone::wave_value(&self.wave_type, phase) * self.volume.clamp(0.0, 1.0)
+    }
+}
+
+/// Peak level the mixer's limiter keeps its output under
+const LIMITER_CEILING: f32 = 1.0;
+
+/// Time the limiter takes to recover about two thirds of its gain after a peak, in milliseconds
+const LIMITER_RELEASE_MS: f32 = 50.0;
+
+/// A peak limiter that turns the mix down when overlapping voices would clip.
+///
+/// The gain drops instantly to keep every sample under the ceiling and recovers
+/// smoothly afterwards, so loud overlaps get quieter instead of distorting.
+struct Limiter {
+    gain: f32,
+    /// Fraction of the gain reduction kept from one frame to the next
+    release: f32,
+}
+
+impl Limiter {
+    fn new(sample_rate: u32) -> Self {
+        #[allow(clippy::cast_precision_loss)]
+        let release_frames = LIMITER_RELEASE_MS * sample_rate as f32 / 1000.0;
+        Self {
+            gain: 1.0,
+            release: (-1.0 / release_frames.max(1.0)).exp(),
+        }
+    }
+
+    fn process(&mut self, value: f32) -> f32 {
+        self.gain = (1.0 - self.gain).mul_add(-self.release, 1.0);
+        if value.abs() * self.gain > LIMITER_CEILING {
+            self.gain = LIMITER_CEILING / value.abs();
+        }
+        value * self.gain
+    }
+}
+
+/// Most voices a real-time mixer holds at once, queued or sounding
+pub(crate) const REAL_TIME_VOICES: usize = 256;
+
+/// A voice that has been placed on the engine clock.
+struct ActiveVoice {
+    voice: Voice,
+    start: u64,
+    end: u64,
+}
+
+/// Mixes scheduled voices into a mono sample buffer, limiting the mix so overlapping voices don't clip.
+pub(crate) struct VoiceMixer {
+    sample_rate: u32,
+    voices: Vec<ActiveVoice>,
+    /// Most voices held at once, or `None` to grow as needed
+    voice_limit: Option<usize>,
+    limiter: Limiter,
+}
+
+impl VoiceMixer {
+    /// Creates a mixer that holds any number of voices, for offline use.
+    pub(crate) fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            voices: Vec::new(),
+            voice_limit: None,
+            limiter: Limiter::new(sample_rate),
+        }
+    }
+
+    /// Creates a mixer for an audio callback, which never allocates after creation.
+    ///
+    /// Its voices live in a fixed pool; when the pool is full, a new voice replaces
+    /// the one that ends first.
+    pub(crate) fn real_time(sample_rate: u32) -> Self {
+        Self {
+            voices: Vec::with_capacity(REAL_TIME_VOICES),
+            voice_limit: Some(REAL_TIME_VOICES),
+            ..Self::new(sample_rate)
+        }
+    }
+
+    /// Adds a voice to the mixer. Voices without a start frame, or whose start
+    /// frame has already passed, begin at `now`.
+    pub(crate) fn add(&mut self, voice: Voice, now: u64) {
+        let start = voice.start_frame.map_or(now, |frame| frame.max(now));
+        let end = start + voice.duration_frames(self.sample_rate);
+        if end <= start {
+            return;
+        }
+        let active = ActiveVoice { voice, start, end };
+        if self
+            .voice_limit
+            .is_some_and(|limit| self.voices.len() >= limit)
+        {
+            if let Some(first_to_end) = self.voices.iter_mut().min_by_key(|active| active.end) {
+                *first_to_end = active;
+            }
+        } else {
+            self.voices.push(active);
+        }
+    }
+
+    /// Fills `output` with mono frames starting at `first_frame` on the engine clock.
+    pub(crate) fn render(&mut self, output: &mut [f32], first_frame: u64) {
+        for (frame, sample) in (first_frame..).zip(output.iter_mut()) {
+            let mut value = 0.0;
+            for active in &self.voices {
+                if (active.start..active.end).contains(&frame) {
+                    value += active
+                        .voice
+                        .sample_at(frame - active.start, self.sample_rate);
+                }
+            }
+            *sample = self.limiter.process(value).clamp(-1.0, 1.0);
+        }
+
+        let last_frame = first_frame + output.len() as u64;
+        self.voices.retain(|active| active.end > last_frame);
+    }
+}